@@ -0,0 +1,83 @@
+// Minimum cost matching of workers to tasks with the Hungarian algorithm
+//
+// costs[worker][task], with at least as many tasks as workers. Returns the task of every worker.
+pub fn assign(costs: &[Vec<f32>]) -> Vec<usize> {
+    let workers = costs.len();
+    if workers == 0 {
+        return vec![];
+    }
+    let tasks = costs[0].len();
+    assert!(tasks >= workers, "every worker needs a task");
+    // Potentials and matching are 1-based, index 0 is the virtual worker the augmenting paths start from
+    let mut worker_potential = vec![0.0f32; workers + 1];
+    let mut task_potential = vec![0.0f32; tasks + 1];
+    let mut task_worker = vec![0usize; tasks + 1];
+    let mut previous_task = vec![0usize; tasks + 1];
+    for worker in 1..=workers {
+        task_worker[0] = worker;
+        let mut task = 0;
+        let mut slack = vec![f32::INFINITY; tasks + 1];
+        let mut used = vec![false; tasks + 1];
+        loop {
+            used[task] = true;
+            let current = task_worker[task];
+            let mut delta = f32::INFINITY;
+            let mut next = 0;
+            for candidate in 1..=tasks {
+                if used[candidate] {
+                    continue;
+                }
+                let reduced = costs[current - 1][candidate - 1] - worker_potential[current] - task_potential[candidate];
+                if reduced < slack[candidate] {
+                    slack[candidate] = reduced;
+                    previous_task[candidate] = task;
+                }
+                if slack[candidate] < delta {
+                    delta = slack[candidate];
+                    next = candidate;
+                }
+            }
+            for candidate in 0..=tasks {
+                if used[candidate] {
+                    worker_potential[task_worker[candidate]] += delta;
+                    task_potential[candidate] -= delta;
+                } else {
+                    slack[candidate] -= delta;
+                }
+            }
+            task = next;
+            if task_worker[task] == 0 {
+                break;
+            }
+        }
+        while task != 0 {
+            let previous = previous_task[task];
+            task_worker[task] = task_worker[previous];
+            task = previous;
+        }
+    }
+    let mut assigned = vec![0; workers];
+    for task in 1..=tasks {
+        if task_worker[task] != 0 {
+            assigned[task_worker[task] - 1] = task - 1;
+        }
+    }
+    assigned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_matrix_takes_the_cheapest_matching() {
+        let costs = vec![vec![4., 1., 3.], vec![2., 0., 5.], vec![3., 2., 2.]];
+        assert_eq!(assign(&costs), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn extra_tasks_are_left_over() {
+        let costs = vec![vec![9., 2., 7.], vec![1., 3., 8.]];
+        assert_eq!(assign(&costs), vec![1, 0]);
+    }
+}
@@ -0,0 +1,353 @@
+// Bundles the crate into the single source file CodinGame accepts
+//
+// Usage: bundle [--crate DIR] [--output FILE] [--no-check]
+//
+// The binary's main.rs becomes the root of the bundle and the library is inlined
+// as a module named after the crate, so `quidditch::` paths in main.rs keep
+// working and `crate::` paths inside the library are redirected to that module.
+// Every `mod x;` is replaced by the contents of its file and `#[cfg(test)]` items
+// are dropped. The output is then compiled with rustc against the dependencies
+// cargo already built to make sure it stands on its own.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+static DEFAULT_OUTPUT: &str = "submission.rs";
+
+fn usage() -> String {
+    "usage: bundle [--crate DIR] [--output FILE] [--no-check]".to_string()
+}
+
+fn main() -> Result<(), String> {
+    let mut crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let mut output = PathBuf::from(DEFAULT_OUTPUT);
+    let mut check = true;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crate" => crate_dir = args.next().map(PathBuf::from).ok_or_else(usage)?,
+            "--output" => output = args.next().map(PathBuf::from).ok_or_else(usage)?,
+            "--no-check" => check = false,
+            _ => return Err(usage()),
+        }
+    }
+    let manifest = read(&crate_dir.join("Cargo.toml"))?;
+    let crate_name = package_name(&manifest).ok_or("Cargo.toml has no package name")?;
+    let source = bundle(&crate_dir.join("src"), &crate_name)?;
+    fs::write(&output, &source).map_err(|e| format!("cannot write {}: {}", output.display(), e))?;
+    println!("Wrote {} ({} lines)", output.display(), source.lines().count());
+    if check {
+        check_standalone(&output, &crate_dir, &dependencies(&manifest))?;
+        println!("{} compiles standalone", output.display());
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))
+}
+
+// main.rs at the root with lib.rs inlined as `mod <crate name>`
+fn bundle(src: &Path, crate_name: &str) -> Result<String, String> {
+    let main = inline_modules(&read(&src.join("main.rs"))?, src)?;
+    let lib = inline_modules(&read(&src.join("lib.rs"))?, src)?;
+    let lib = replace_path(&lib, "crate", &format!("crate::{}", crate_name));
+    Ok(format!("{}\n#[allow(dead_code, unused_imports)]\nmod {} {{\n{}}}\n", main.trim_end(), crate_name, indent(&lib)))
+}
+
+// Source with test items removed and every `mod x;` replaced by an inline module
+fn inline_modules(source: &str, dir: &Path) -> Result<String, String> {
+    let source = strip_tests(source);
+    let mut out = String::new();
+    for line in source.lines() {
+        match module_declaration(line) {
+            Some((visibility, name)) => {
+                let path = module_file(dir, name)?;
+                let child_dir = match path.file_name().and_then(|f| f.to_str()) {
+                    Some("mod.rs") => path.parent().unwrap().to_path_buf(),
+                    _ => dir.join(name),
+                };
+                let body = inline_modules(&read(&path)?, &child_dir)?;
+                let margin = &line[..line.len() - line.trim_start().len()];
+                out.push_str(&format!("{}{}mod {} {{\n", margin, visibility, name));
+                for body_line in indent(&body).lines() {
+                    if !body_line.is_empty() {
+                        out.push_str(margin);
+                    }
+                    out.push_str(body_line);
+                    out.push('\n');
+                }
+                out.push_str(&format!("{}}}\n", margin));
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Visibility and name of a `mod name;` line
+fn module_declaration(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let declaration = line.strip_suffix(';')?;
+    let split = declaration.rfind("mod ")?;
+    let (visibility, name) = (&declaration[..split], declaration[split + 4..].trim());
+    let valid_visibility = visibility.is_empty() || visibility.starts_with("pub") && visibility.ends_with(' ');
+    let valid_name = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if valid_visibility && valid_name {
+        Some((visibility, name))
+    } else {
+        None
+    }
+}
+
+fn module_file(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let candidates = [dir.join(format!("{}.rs", name)), dir.join(name).join("mod.rs")];
+    candidates.iter()
+              .find(|path| path.is_file())
+              .cloned()
+              .ok_or_else(|| format!("no file for module {} in {}", name, dir.display()))
+}
+
+fn indent(source: &str) -> String {
+    source.lines()
+          .map(|line| if line.is_empty() { "\n".to_string() } else { format!("    {}\n", line) })
+          .collect()
+}
+
+// Which bytes are code rather than comments, strings or character literals
+fn code_mask(source: &str) -> Vec<bool> {
+    let bytes = source.as_bytes();
+    let mut mask = vec![true; bytes.len()];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'/' if next == Some(b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if next == Some(b'*') => {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            b'r' if matches!(next, Some(b'"') | Some(b'#')) && (i == 0 || !is_ident(bytes[i - 1])) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) != Some(&b'"') {
+                    i += 1;
+                    continue;
+                }
+                let mut terminator = vec![b'"'];
+                terminator.extend(std::iter::repeat_n(b'#', hashes));
+                i += 2 + hashes;
+                while i < bytes.len() && !bytes[i..].starts_with(&terminator) {
+                    i += 1;
+                }
+                i += terminator.len();
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            // A quote is a character literal when it closes right after one (escaped) character, a lifetime otherwise
+            b'\'' => {
+                let length = if next == Some(b'\\') {
+                    bytes.get(i + 3..).and_then(|rest| rest.iter().position(|&b| b == b'\'')).map(|p| p + 4)
+                } else {
+                    let width = source[i + 1..].chars().next().map_or(1, char::len_utf8);
+                    (bytes.get(i + 1 + width) == Some(&b'\'')).then_some(width + 2)
+                };
+                match length {
+                    Some(length) => i += length,
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        let end = i.min(bytes.len());
+        mask[start..end].iter_mut().for_each(|code| *code = false);
+    }
+    mask
+}
+
+fn is_ident(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+// Removes every item annotated with #[cfg(test)], up to its closing brace or semicolon
+fn strip_tests(source: &str) -> String {
+    let mask = code_mask(source);
+    let bytes = source.as_bytes();
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut search = 0;
+    while let Some(found) = source[search..].find("#[cfg(test)]").map(|p| p + search) {
+        search = found + 1;
+        if !mask[found] || found < copied {
+            continue;
+        }
+        let mut depth = 0;
+        let mut end = found + "#[cfg(test)]".len();
+        while end < bytes.len() {
+            if mask[end] {
+                match bytes[end] {
+                    b'{' => depth += 1,
+                    b'}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    b';' if depth == 0 => break,
+                    _ => {}
+                }
+            }
+            end += 1;
+        }
+        let line_start = source[..found].rfind('\n').map_or(0, |p| p + 1);
+        let start = if source[line_start..found].trim().is_empty() { line_start } else { found };
+        let end = (end + 1).min(bytes.len());
+        let end = if source[end..].starts_with('\n') { end + 1 } else { end };
+        out.push_str(&source[copied..start]);
+        copied = end;
+        search = end;
+    }
+    out.push_str(&source[copied..]);
+    out
+}
+
+// Rewrites paths starting with `from::` in code, leaving comments and strings alone
+fn replace_path(source: &str, from: &str, to: &str) -> String {
+    let mask = code_mask(source);
+    let bytes = source.as_bytes();
+    let pattern = format!("{}::", from);
+    let mut out = String::new();
+    let mut copied = 0;
+    for (found, _) in source.match_indices(&pattern) {
+        let preceded = found > 0 && (is_ident(bytes[found - 1]) || bytes[found - 1] == b'$' || bytes[found - 1] == b':');
+        if mask[found] && !preceded {
+            out.push_str(&source[copied..found]);
+            out.push_str(to);
+            out.push_str("::");
+            copied = found + pattern.len();
+        }
+    }
+    out.push_str(&source[copied..]);
+    out
+}
+
+// The name of [package] as it is used in paths
+fn package_name(manifest: &str) -> Option<String> {
+    section_keys(manifest, "package").into_iter()
+                                     .find(|(key, _)| key == "name")
+                                     .map(|(_, value)| value.trim_matches('"').replace('-', "_"))
+}
+
+fn dependencies(manifest: &str) -> Vec<String> {
+    section_keys(manifest, "dependencies").into_iter().map(|(key, _)| key.replace('-', "_")).collect()
+}
+
+// Key and raw value of every `key = value` line in a manifest section
+fn section_keys(manifest: &str, section: &str) -> Vec<(String, String)> {
+    let header = format!("[{}]", section);
+    manifest.lines()
+            .skip_while(|line| line.trim() != header)
+            .skip(1)
+            .take_while(|line| !line.trim().starts_with('['))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect()
+}
+
+// Compiles the bundle with rustc, linking the rlibs of the dependencies from cargo's target directories
+fn check_standalone(path: &Path, crate_dir: &Path, dependencies: &[String]) -> Result<(), String> {
+    let mut deps_dirs = vec![];
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|d| d.join("deps"))) {
+        deps_dirs.push(dir);
+    }
+    deps_dirs.push(crate_dir.join("target").join("release").join("deps"));
+    deps_dirs.push(crate_dir.join("target").join("debug").join("deps"));
+    let out_dir = std::env::temp_dir().join(format!("bundle-check-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).map_err(|e| format!("cannot create {}: {}", out_dir.display(), e))?;
+    let mut rustc = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()));
+    rustc.args(["--edition", "2018", "--crate-type", "bin", "--emit", "metadata", "--out-dir"])
+         .arg(&out_dir)
+         .arg(path);
+    for dependency in dependencies {
+        let rlib = deps_dirs.iter()
+                            .find_map(|dir| newest_rlib(dir, dependency))
+                            .ok_or_else(|| format!("no built {} found, run cargo build first", dependency))?;
+        rustc.arg("--extern").arg(format!("{}={}", dependency, rlib.display()));
+        rustc.arg("-L").arg(format!("dependency={}", rlib.parent().unwrap().display()));
+    }
+    let status = rustc.status().map_err(|e| format!("cannot run rustc: {}", e));
+    let _ = fs::remove_dir_all(&out_dir);
+    if !status?.success() {
+        return Err(format!("{} does not compile standalone", path.display()));
+    }
+    Ok(())
+}
+
+fn newest_rlib(dir: &Path, name: &str) -> Option<PathBuf> {
+    let prefix = format!("lib{}-", name);
+    fs::read_dir(dir).ok()?
+                     .filter_map(|entry| entry.ok())
+                     .filter(|entry| {
+                         let file_name = entry.file_name().to_string_lossy().to_string();
+                         file_name.starts_with(&prefix) && file_name.ends_with(".rlib")
+                     })
+                     .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+                     .map(|entry| entry.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_tests_removes_test_items_only() {
+        let source = "fn kept() {}\n\
+                      #[cfg(test)]\n\
+                      mod tests {\n    fn braces() -> &'static str { \"}\" }\n}\n\
+                      // #[cfg(test)] in a comment stays\n\
+                      #[cfg(test)]\n\
+                      use std::fmt;\n\
+                      fn also_kept() { let s = \"#[cfg(test)]\"; }\n";
+        assert_eq!(strip_tests(source), "fn kept() {}\n\
+                                         // #[cfg(test)] in a comment stays\n\
+                                         fn also_kept() { let s = \"#[cfg(test)]\"; }\n");
+    }
+
+    #[test]
+    fn replace_path_leaves_comments_strings_and_longer_paths() {
+        let source = "use crate::state; // crate::x\nlet s = \"crate::y\"; my_crate::z();\n";
+        assert_eq!(replace_path(source, "crate", "crate::quidditch"),
+                   "use crate::quidditch::state; // crate::x\nlet s = \"crate::y\"; my_crate::z();\n");
+    }
+}
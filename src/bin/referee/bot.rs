@@ -0,0 +1,124 @@
+// What plays one side of a match: a bot executable driven over stdin and stdout, or one of the crate's
+// strategies playing the same protocol in this process
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use quidditch::protocol::{ProtocolReader, TurnSource};
+use quidditch::{strategy, State, Strategy};
+
+// Prefix of the players given by strategy name rather than by command
+pub static BUILTIN_PREFIX: &str = "builtin:";
+
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Bot {
+    // The command is split on whitespace so arguments can be passed to the bot
+    pub fn spawn(command: &str) -> Result<Bot, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| "empty bot command".to_string())?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("cannot start '{}': {}", command, e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        // Reading on a separate thread lets the referee stop waiting after a timeout
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() { break; },
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Bot { child, stdin, lines })
+    }
+    pub fn send(&mut self, lines: &[String]) -> Result<(), String> {
+        for line in lines {
+            writeln!(self.stdin, "{}", line).map_err(|e| format!("cannot write to bot: {}", e))?;
+        }
+        self.stdin.flush().map_err(|e| format!("cannot write to bot: {}", e))
+    }
+    // Waits for the next output line until the deadline, forever when there is none
+    pub fn receive(&self, deadline: Option<Instant>) -> Result<String, String> {
+        match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                self.lines.recv_timeout(timeout).map_err(|e| match e {
+                    RecvTimeoutError::Timeout => "timeout before the end of the turn".to_string(),
+                    RecvTimeoutError::Disconnected => "bot exited".to_string(),
+                })
+            }
+            None => self.lines.recv().map_err(|_| "bot exited".to_string()),
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub enum Player {
+    Process(Bot),
+    // Not timed: the strategy keeps to its time budget by itself and a busy machine only shortens its search
+    Builtin { strategy: Box<dyn Strategy>, state: Option<Box<State>> },
+}
+
+impl Player {
+    // `builtin:NAME` plays the strategy of that name, anything else is a bot command
+    pub fn new(spec: &str) -> Result<Player, String> {
+        match spec.strip_prefix(BUILTIN_PREFIX) {
+            Some(name) => Ok(Player::Builtin { strategy: strategy::from_name(name)?, state: None }),
+            None => Bot::spawn(spec).map(Player::Process),
+        }
+    }
+    // Refuses an unknown builtin strategy before any game is played with it
+    pub fn check(spec: &str) -> Result<(), String> {
+        match spec.strip_prefix(BUILTIN_PREFIX) {
+            Some(name) => strategy::from_name(name).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+    pub fn is_process(spec: &str) -> bool {
+        !spec.starts_with(BUILTIN_PREFIX)
+    }
+    pub fn start(&mut self, team: usize) -> Result<(), String> {
+        match self {
+            Player::Process(bot) => bot.send(&[team.to_string()]),
+            Player::Builtin { state, .. } => {
+                *state = Some(Box::new(State::new(team as i32)));
+                Ok(())
+            }
+        }
+    }
+    // Command lines of a turn, the timeout counting for the whole turn
+    pub fn play(&mut self, input: &[String], wizards: usize, timeout: Option<Duration>) -> Result<Vec<String>, String> {
+        match self {
+            Player::Process(bot) => {
+                bot.send(input)?;
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                (0..wizards).map(|_| bot.receive(deadline)).collect()
+            }
+            Player::Builtin { strategy, state } => {
+                let state = state.as_mut().ok_or("not started")?;
+                let turn = ProtocolReader::new(Cursor::new(input.join("\n"))).read_turn().map_err(|e| e.to_string())?;
+                state.update(&turn, state.turn == 0);
+                Ok(state.act_turn(strategy.as_mut()))
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Fantastic Bits match: spawn, scoring, end of game and the protocol lines of both teams. The physics
+// are the bot's own, a simulation seen from team 0.
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use quidditch::command::Command;
+use quidditch::physics::Simulation;
+use quidditch::protocol::{EntityInput, TurnInput};
+use quidditch::spell_tracker::Effect;
+use quidditch::spells::Spell;
+use quidditch::{EntityType, State, Vector2, HEIGHT, MAX_TURNS, SNAFFLE_RADIUS, WIDTH, WIZARD_RADIUS};
+
+// Parses one output line of a bot, anything after the expected arguments is a debug message
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |i: usize| -> Result<i32, String> {
+        words.get(i)
+             .ok_or_else(|| format!("missing argument in '{}'", line))?
+             .parse::<i32>()
+             .map_err(|_| format!("bad number in '{}'", line))
+    };
+    let point = |i: usize| -> Result<Vector2, String> { Ok(Vector2::new(number(i)? as f32, number(i + 1)? as f32)) };
+    let spell = |spell: Spell| -> Result<Command, String> { Ok(Command::cast(spell, number(1)?)) };
+    match words.first().copied() {
+        Some("MOVE") => Ok(Command::Move(point(1)?, number(3)?)),
+        Some("THROW") => Ok(Command::Throw(point(1)?, number(3)?)),
+        Some("WINGARDIUM") => Ok(Command::wingardium(number(1)?, point(2)?, number(4)?)),
+        Some("OBLIVIATE") => spell(Spell::Obliviate),
+        Some("PETRIFICUS") => spell(Spell::Petrificus),
+        Some("ACCIO") => spell(Spell::Accio),
+        Some("FLIPENDO") => spell(Spell::Flipendo),
+        _ => Err(format!("unknown command '{}'", line)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub turn: i32,
+    snaffle_count: i32,
+    simulation: Simulation,
+}
+
+impl Game {
+    pub fn new(seed: u64) -> Game {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (width, height) = (WIDTH - 1, HEIGHT - 1);
+        let mut spawns = vec![
+            (EntityType::Wizard, 1000, 2250),
+            (EntityType::Wizard, 1000, 5250),
+            (EntityType::Opponent, width - 1000, 5250),
+            (EntityType::Opponent, width - 1000, 2250),
+        ];
+        let snaffle_count = if rng.gen::<bool>() { 5 } else { 7 };
+        if snaffle_count % 2 == 1 {
+            spawns.push((EntityType::Snaffle, width / 2, height / 2));
+        }
+        // Remaining snaffles come in pairs mirrored through the center of the map
+        let clear = |spawns: &[(EntityType, i32, i32)], x: i32, y: i32| spawns.iter().all(|(kind, sx, sy)| {
+            let reach = if *kind == EntityType::Snaffle { SNAFFLE_RADIUS } else { WIZARD_RADIUS } + 300.;
+            Vector2::new(x as f32, y as f32).distance(Vector2::new(*sx as f32, *sy as f32)) > reach
+        });
+        while spawns.len() < 4 + snaffle_count as usize {
+            let x = rng.gen_range(2000, width / 2 - 1000);
+            let y = rng.gen_range(500, height - 500);
+            if clear(&spawns, x, y) && clear(&spawns, width - x, height - y) {
+                spawns.push((EntityType::Snaffle, x, y));
+                spawns.push((EntityType::Snaffle, width - x, height - y));
+            }
+        }
+        spawns.push((EntityType::Bludger, 7450, 3750));
+        spawns.push((EntityType::Bludger, 8550, 3750));
+        let entities = spawns.into_iter().enumerate().map(|(id, (entity_type, x, y))| {
+            let state = if entity_type == EntityType::Bludger { -1 } else { 0 };
+            EntityInput { id: id as i32, entity_type, x, y, vx: 0, vy: 0, state }
+        }).collect();
+        let input = TurnInput {
+            my_score: 0, my_magic: 0, opponent_score: 0, opponent_magic: 0, entities, received: Instant::now(),
+        };
+        let mut state = State::new(0);
+        state.update(&input, true);
+        Game { turn: 0, snaffle_count, simulation: Simulation::new(&state) }
+    }
+    pub fn scores(&self) -> [i32; 2] {
+        self.simulation.scored
+    }
+    fn magic(&self, team: usize) -> i32 {
+        if team == 0 { self.simulation.state.magic } else { self.simulation.state.opponent_magic }
+    }
+    // Lines sent to a bot at the start of each turn, in the CodinGame protocol
+    pub fn input_for(&self, team: usize) -> Vec<String> {
+        let entities = &self.simulation.state.entities;
+        let scores = self.scores();
+        let mut lines = vec![
+            format!("{} {}", scores[team], self.magic(team)),
+            format!("{} {}", scores[1 - team], self.magic(1 - team)),
+            entities.len().to_string(),
+        ];
+        for entity in entities {
+            let (kind, state) = match entity.entity_type {
+                EntityType::Wizard | EntityType::Opponent => {
+                    let ours = (entity.entity_type == EntityType::Wizard) == (team == 0);
+                    (if ours { "WIZARD" } else { "OPPONENT_WIZARD" }, entity.holding().is_some() as i32)
+                }
+                EntityType::Snaffle => ("SNAFFLE", entity.held_by().is_some() as i32),
+                EntityType::Bludger => ("BLUDGER", entity.last_victim().unwrap_or(-1)),
+            };
+            let collider = &entity.collider;
+            lines.push(format!("{} {} {} {} {} {} {}", entity.id, kind, collider.pos.x as i32, collider.pos.y as i32,
+                               collider.vel.x as i32, collider.vel.y as i32, state));
+        }
+        lines
+    }
+    // Ids of the wizards of a team, in the order their commands are expected
+    pub fn wizards_of(&self, team: usize) -> Vec<i32> {
+        let state = &self.simulation.state;
+        let wizards = if team == 0 { state.wizards() } else { state.opponents() };
+        wizards.iter().map(|w| w.id).collect()
+    }
+    // Plays one turn with the commands of each team, in wizard order
+    pub fn play_turn(&mut self, commands: &[Vec<Command>; 2]) {
+        for (team, team_commands) in commands.iter().enumerate() {
+            for (wizard, command) in self.wizards_of(team).into_iter().zip(team_commands.iter()) {
+                match *command {
+                    Command::Move(dest, thrust) => self.simulation.move_wizard(wizard, dest, thrust),
+                    Command::Throw(dest, power) => self.simulation.throw(wizard, dest, power),
+                    Command::Spell { kind: Effect::Wingardium, target, dest, power } => {
+                        self.simulation.wingardium(wizard, target, dest, power)
+                    }
+                    Command::Spell { kind: Effect::Spell(spell), target, .. } => self.simulation.cast(wizard, spell, target),
+                }
+            }
+        }
+        self.simulation.step();
+        self.turn += 1;
+    }
+    // Some(None) on a draw, None while the game goes on
+    pub fn winner(&self) -> Option<Option<usize>> {
+        let scores = self.scores();
+        let half = self.snaffle_count / 2;
+        let remaining = self.simulation.state.snaffles().len();
+        let leader = if scores[0] > scores[1] {
+            Some(0)
+        } else if scores[1] > scores[0] {
+            Some(1)
+        } else {
+            None
+        };
+        if scores.iter().any(|&s| s > half) || remaining == 0 || self.turn >= MAX_TURNS {
+            Some(leader)
+        } else {
+            None
+        }
+    }
+}
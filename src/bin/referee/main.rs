@@ -0,0 +1,107 @@
+// Local referee playing a Fantastic Bits match between two bots
+//
+// Usage: referee [--seed N] [--no-timeout] <team 0 player> <team 1 player>
+//        referee tournament ... (see tournament.rs)
+//
+// A player is a bot command, or `builtin:NAME` for a strategy of this crate played in this process.
+mod bot;
+mod game;
+mod tournament;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bot::Player;
+use game::{parse_command, Game};
+
+static FIRST_TURN_TIMEOUT_MS: u64 = 1000;
+static TURN_TIMEOUT_MS: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    pub seed: u64,
+    pub scores: [i32; 2],
+    pub turns: i32,
+    // None on a draw
+    pub winner: Option<usize>,
+    // Why a bot lost before the end of the game
+    pub forfeit: Option<String>,
+}
+
+pub fn play_match(players: [&str; 2], seed: u64, timeouts: bool) -> Result<MatchResult, String> {
+    let mut game = Game::new(seed);
+    let mut players = [Player::new(players[0])?, Player::new(players[1])?];
+    let forfeit = |game: &Game, team: usize, reason: String| MatchResult {
+        seed,
+        scores: game.scores(),
+        turns: game.turn,
+        winner: Some(1 - team),
+        forfeit: Some(format!("team {} {}", team, reason)),
+    };
+    for (team, player) in players.iter_mut().enumerate() {
+        if let Err(reason) = player.start(team) {
+            return Ok(forfeit(&game, team, reason));
+        }
+    }
+    loop {
+        if let Some(winner) = game.winner() {
+            return Ok(MatchResult { seed, scores: game.scores(), turns: game.turn, winner, forfeit: None });
+        }
+        let timeout = if !timeouts {
+            None
+        } else if game.turn == 0 {
+            Some(Duration::from_millis(FIRST_TURN_TIMEOUT_MS))
+        } else {
+            Some(Duration::from_millis(TURN_TIMEOUT_MS))
+        };
+        let mut commands = [vec![], vec![]];
+        for team in 0..2 {
+            let lines = players[team].play(&game.input_for(team), game.wizards_of(team).len(), timeout);
+            match lines.and_then(|lines| lines.iter().map(|line| parse_command(line)).collect()) {
+                Ok(team_commands) => commands[team] = team_commands,
+                Err(reason) => return Ok(forfeit(&game, team, reason)),
+            }
+        }
+        game.play_turn(&commands);
+    }
+}
+
+fn usage() -> String {
+    "usage: referee [--seed N] [--no-timeout] <team 0 player> <team 1 player>".to_string()
+}
+
+fn main() -> Result<(), String> {
+    let all_args: Vec<String> = std::env::args().skip(1).collect();
+    if all_args.first().map(|a| a.as_str()) == Some("tournament") {
+        return tournament::run(&all_args[1..]);
+    }
+    let mut seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut timeouts = true;
+    let mut commands = vec![];
+    let mut args = all_args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = args.next().and_then(|s| s.parse().ok()).ok_or_else(usage)?,
+            "--no-timeout" => timeouts = false,
+            _ => commands.push(arg),
+        }
+    }
+    if commands.len() != 2 {
+        return Err(usage());
+    }
+    for command in &commands {
+        Player::check(command)?;
+    }
+    let result = play_match([&commands[0], &commands[1]], seed, timeouts)?;
+    println!("Seed {} after {} turns", result.seed, result.turns);
+    for (team, command) in commands.iter().enumerate() {
+        println!("Team {} ({}): {}", team, command, result.scores[team]);
+    }
+    if let Some(reason) = &result.forfeit {
+        println!("Forfeit: {}", reason);
+    }
+    match result.winner {
+        Some(team) => println!("Winner: team {} ({})", team, commands[team]),
+        None => println!("Draw"),
+    }
+    Ok(())
+}
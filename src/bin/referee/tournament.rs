@@ -0,0 +1,180 @@
+// Round robin of seeded matches between players, every seed is played from both sides
+//
+// Usage: referee tournament [--games N] [--seed N] [--threads N] [--no-timeout] <player> <player>...
+//
+// Builtin players are played on every core by default. Matches involving a bot executable are played one
+// at a time unless --threads says otherwise, so that bots do not lose turns to each other's load.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use crate::bot::Player;
+use crate::{play_match, MatchResult};
+
+static DEFAULT_GAMES: u64 = 100;
+static Z_95: f64 = 1.96;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fixture {
+    home: usize,
+    away: usize,
+    seed: u64,
+}
+
+// Results of the first bot of a pairing against the second one
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Record {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    forfeits: u32,
+}
+
+impl Record {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+    // Points per game counting a draw as half a win, with a normal approximation interval
+    fn score(&self) -> (f64, f64) {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return (0.0, 0.0);
+        }
+        let score = (self.wins as f64 + self.draws as f64 / 2.0) / n;
+        (score, Z_95 * (score * (1.0 - score) / n).sqrt())
+    }
+    // Wilson interval of the win rate
+    fn win_rate(&self) -> (f64, f64, f64) {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        let p = self.wins as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        (p, center - spread, center + spread)
+    }
+}
+
+fn usage() -> String {
+    "usage: referee tournament [--games N] [--seed N] [--threads N] [--no-timeout] <player> <player>...".to_string()
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut games = DEFAULT_GAMES;
+    let mut base_seed = 0;
+    let mut threads = None;
+    let mut timeouts = true;
+    let mut bots = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = args.next().and_then(|s| s.parse().ok()).ok_or_else(usage)?,
+            "--seed" => base_seed = args.next().and_then(|s| s.parse().ok()).ok_or_else(usage)?,
+            "--threads" => threads = Some(args.next().and_then(|s| s.parse().ok()).ok_or_else(usage)?),
+            "--no-timeout" => timeouts = false,
+            _ => bots.push(arg.clone()),
+        }
+    }
+    let threads = match threads {
+        Some(threads) => threads,
+        None if bots.iter().any(|b| Player::is_process(b)) => 1,
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+    if bots.len() < 2 || threads == 0 {
+        return Err(usage());
+    }
+    for bot in &bots {
+        Player::check(bot)?;
+    }
+    let mut fixtures = vec![];
+    for a in 0..bots.len() {
+        for b in (a + 1)..bots.len() {
+            for seed in base_seed..base_seed + games {
+                fixtures.push(Fixture { home: a, away: b, seed });
+                fixtures.push(Fixture { home: b, away: a, seed });
+            }
+        }
+    }
+    let results = play_fixtures(&bots, &fixtures, threads, timeouts);
+    let mut records = vec![vec![Record::default(); bots.len()]; bots.len()];
+    let mut errors = 0;
+    for (fixture, result) in fixtures.iter().zip(results.iter()) {
+        let result = match result {
+            Ok(result) => result,
+            Err(error) => {
+                eprintln!("Seed {} failed: {}", fixture.seed, error);
+                errors += 1;
+                continue;
+            }
+        };
+        let (a, b) = (fixture.home.min(fixture.away), fixture.home.max(fixture.away));
+        let record = &mut records[a][b];
+        match result.winner.map(|team| if team == 0 { fixture.home } else { fixture.away }) {
+            Some(winner) if winner == a => record.wins += 1,
+            Some(_) => record.losses += 1,
+            None => record.draws += 1,
+        }
+        if result.forfeit.is_some() {
+            record.forfeits += 1;
+        }
+    }
+    for a in 0..bots.len() {
+        for b in (a + 1)..bots.len() {
+            let record = &records[a][b];
+            let n = record.games().max(1) as f64;
+            let (win_rate, low, high) = record.win_rate();
+            let (score, margin) = record.score();
+            println!("{} vs {}", bots[a], bots[b]);
+            println!("  {} games: {} wins ({:.1}%), {} draws ({:.1}%), {} losses ({:.1}%), {} forfeits",
+                     record.games(),
+                     record.wins, 100.0 * record.wins as f64 / n,
+                     record.draws, 100.0 * record.draws as f64 / n,
+                     record.losses, 100.0 * record.losses as f64 / n,
+                     record.forfeits);
+            println!("  win rate {:.1}% (95% CI {:.1}% - {:.1}%), score {:.1}% +- {:.1}%",
+                     100.0 * win_rate, 100.0 * low, 100.0 * high, 100.0 * score, 100.0 * margin);
+        }
+    }
+    if errors > 0 {
+        return Err(format!("{} games could not be played", errors));
+    }
+    Ok(())
+}
+
+// Plays every fixture on a pool of worker threads, results come back in fixture order
+fn play_fixtures(bots: &[String], fixtures: &[Fixture], threads: usize, timeouts: bool)
+                 -> Vec<Result<MatchResult, String>> {
+    let bots = Arc::new(bots.to_vec());
+    let queue = Arc::new(fixtures.to_vec());
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..threads).map(|_| {
+        let (bots, queue, next, sender) = (bots.clone(), queue.clone(), next.clone(), sender.clone());
+        thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let fixture = match queue.get(index) {
+                Some(fixture) => fixture,
+                None => break,
+            };
+            let result = play_match([&bots[fixture.home], &bots[fixture.away]], fixture.seed, timeouts);
+            if sender.send((index, result)).is_err() {
+                break;
+            }
+        })
+    }).collect();
+    drop(sender);
+    let mut results: Vec<Option<Result<MatchResult, String>>> = vec![None; fixtures.len()];
+    for (played, (index, result)) in receiver.iter().enumerate() {
+        results[index] = Some(result);
+        eprint!("\rPlayed {}/{}", played + 1, fixtures.len());
+    }
+    eprintln!();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    results.into_iter()
+           .map(|r| r.unwrap_or_else(|| Err("worker stopped".to_string())))
+           .collect()
+}
@@ -0,0 +1,252 @@
+// What a wizard is told to do for a turn, checked against the rules before it becomes a protocol line
+use crate::genetic::GeneAction;
+use crate::spell_tracker::Effect;
+use crate::spells::Spell;
+use crate::{Entity, State, Vector2, HEIGHT, MAX_POWER, MAX_THRUST, WIDTH};
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum Command {
+    Move(Vector2, i32),
+    Throw(Vector2, i32),
+    // Power is the magic spent, only Wingardium chooses it and pushes its target towards the destination
+    Spell { kind: Effect, target: i32, dest: Vector2, power: i32 },
+}
+
+impl Command {
+    pub fn wingardium(target: i32, dest: Vector2, power: i32) -> Command {
+        Command::Spell { kind: Effect::Wingardium, target, dest, power }
+    }
+    pub fn cast(spell: Spell, target: i32) -> Command {
+        Command::Spell { kind: Effect::Spell(spell), target, dest: Vector2::new(0., 0.), power: spell.cost() }
+    }
+    // Entity a spell is cast on
+    pub fn spell_target(&self) -> Option<i32> {
+        match self {
+            Command::Spell { target, .. } => Some(*target),
+            _ => None,
+        }
+    }
+    // Magic the command spends, a fixed cost spell costs the same whatever its power says
+    pub fn magic(&self) -> i32 {
+        match self {
+            Command::Spell { kind: Effect::Spell(spell), .. } => spell.cost(),
+            Command::Spell { kind: Effect::Wingardium, power, .. } => *power,
+            _ => 0,
+        }
+    }
+    pub fn line(&self) -> String {
+        match self {
+            Command::Move(dest, thrust) => format!("MOVE {} {} {} MOVING", coordinate(dest.x), coordinate(dest.y), thrust),
+            Command::Throw(dest, power) => {
+                format!("THROW {} {} {} THROWING", coordinate(dest.x), coordinate(dest.y), power)
+            }
+            Command::Spell { kind: Effect::Wingardium, target, dest, power } => {
+                format!("WINGARDIUM {} {} {} {} DOING SPELLS LOL", target, coordinate(dest.x), coordinate(dest.y), power)
+            }
+            Command::Spell { kind: Effect::Spell(spell), target, .. } => {
+                format!("{} {} CASTING {}", spell.keyword(), target, spell.keyword())
+            }
+        }
+    }
+}
+
+impl From<GeneAction> for Command {
+    fn from(action: GeneAction) -> Command {
+        match action {
+            GeneAction::Move(dest, thrust) => Command::Move(dest, thrust),
+            GeneAction::Throw(dest, power) => Command::Throw(dest, power),
+            GeneAction::Cast(spell, target) => Command::cast(spell, target),
+        }
+    }
+}
+
+// Rounded rather than truncated, a destination close to the origin would turn otherwise
+fn coordinate(value: f32) -> i32 {
+    value.round() as i32
+}
+
+// Exactly one valid command for every wizard of ours, in the order of State::wizards. Destinations are
+// kept within reach of the map along the same heading, thrust and power are capped, and a throw without
+// a snaffle, a spell the magic left cannot pay for or a spell on a target it cannot act on is replaced by a move.
+pub fn validate(state: &State, commands: Vec<Command>) -> Vec<Command> {
+    let mut magic_left = state.magic;
+    let mut commands = commands.into_iter();
+    state.wizards().iter().map(|wizard| {
+        let origin = wizard.collider.pos;
+        let command = match commands.next() {
+            Some(Command::Move(dest, thrust)) => Command::Move(within_bounds(origin, dest), thrust.max(0).min(MAX_THRUST)),
+            Some(Command::Throw(dest, power)) if wizard.has_snaffle() => {
+                Command::Throw(within_bounds(origin, dest), power.max(0).min(MAX_POWER))
+            }
+            Some(Command::Spell { kind, target, dest, power }) if castable(state, wizard, kind, target, power, magic_left) => {
+                match kind {
+                    Effect::Spell(spell) => Command::cast(spell, target),
+                    Effect::Wingardium => {
+                        let target_pos = state.entities.iter().find(|e| e.id == target).unwrap().collider.pos;
+                        Command::wingardium(target, within_bounds(target_pos, dest), power)
+                    }
+                }
+            }
+            refused => {
+                if let Some(refused) = refused {
+                    eprintln!("Refused {:?} for wizard {}", refused, wizard.id);
+                }
+                fallback(state, wizard)
+            }
+        };
+        magic_left -= command.magic();
+        command
+    }).collect()
+}
+
+// Whether the magic left pays for a spell on an entity other than the caster that the spell can act on
+fn castable(state: &State, wizard: &Entity, kind: Effect, target: i32, power: i32, magic_left: i32) -> bool {
+    let target = match state.entities.iter().find(|e| e.id == target && e.id != wizard.id) {
+        Some(target) => target,
+        None => return false,
+    };
+    let (magic, allowed) = match kind {
+        Effect::Spell(spell) => (spell.cost(), spell.can_target(&target.entity_type)),
+        Effect::Wingardium => (power, true),
+    };
+    allowed && magic > 0 && magic <= magic_left
+}
+
+// Towards the closest snaffle, or nowhere at all when none is left
+fn fallback(state: &State, wizard: &Entity) -> Command {
+    match state.closest_snaffle(wizard.collider.pos) {
+        Some(snaffle) => Command::Move(snaffle.collider.pos, MAX_THRUST),
+        None => Command::Move(wizard.collider.pos, 0),
+    }
+}
+
+// The point where the segment from an origin inside the map to the destination leaves the bounds, or the
+// destination itself when it is inside. The bounds reach a map width and height past every edge so that
+// a destination pulled back still lies far enough from the origin for its heading to survive rounding.
+fn within_bounds(origin: Vector2, dest: Vector2) -> Vector2 {
+    let (width, height) = ((WIDTH - 1) as f32, (HEIGHT - 1) as f32);
+    let heading = dest.sub(origin);
+    let limit = |from: f32, delta: f32, size: f32| {
+        if from + delta > 2.0 * size {
+            (2.0 * size - from) / delta
+        } else if from + delta < -size {
+            (-size - from) / delta
+        } else {
+            1.0
+        }
+    };
+    let t = limit(origin.x, heading.x, width).min(limit(origin.y, heading.y, height)).max(0.0);
+    if t < 1.0 { origin.add(heading.mul_num(t)) } else { dest }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collider, EntityType, BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS};
+    use crate::{SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS, WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+    // Our wizards 0 and 1, an opponent 2, a snaffle 4 and a bludger 5, with 10 magic
+    fn state() -> State {
+        let body = |id: i32, entity_type: EntityType, x: f32, y: f32| {
+            let (friction, mass, radius) = match entity_type {
+                EntityType::Snaffle => (SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+                EntityType::Bludger => (BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS),
+                _ => (WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+            };
+            let collider = Collider::new(Vector2::new(x, y), Vector2::new(0., 0.), friction, mass, radius);
+            Entity::new(id, entity_type, collider, 0)
+        };
+        let mut state = State::new(0);
+        state.entities = vec![
+            body(0, EntityType::Wizard, 1000., 2250.),
+            body(1, EntityType::Wizard, 1000., 5250.),
+            body(2, EntityType::Opponent, 15000., 5250.),
+            body(4, EntityType::Snaffle, 5000., 3750.),
+            body(5, EntityType::Bludger, 7450., 3750.),
+        ];
+        state.magic = 10;
+        state
+    }
+
+    #[test]
+    fn out_of_map_move_keeps_its_heading() {
+        let commands = validate(&state(), vec![
+            Command::Move(Vector2::new(1000., -90000.), 200),
+            Command::Move(Vector2::new(3000., 5250.), 100),
+        ]);
+        assert_eq!(commands, vec![
+            Command::Move(Vector2::new(1000., -7500.), MAX_THRUST),
+            Command::Move(Vector2::new(3000., 5250.), 100),
+        ]);
+        // Next to the wall the heading is still worth a full map height
+        assert_eq!(within_bounds(Vector2::new(400., 500.), Vector2::new(-3600., -1e6)).y, -7500.);
+    }
+
+    #[test]
+    fn throw_without_a_snaffle_becomes_a_move_to_the_closest_one() {
+        let commands = validate(&state(), vec![
+            Command::Throw(Vector2::new(16000., 3750.), MAX_POWER),
+            Command::Move(Vector2::new(3000., 5250.), 100),
+        ]);
+        assert_eq!(commands[0], Command::Move(Vector2::new(5000., 3750.), MAX_THRUST));
+    }
+
+    #[test]
+    fn spells_are_paid_in_wizard_order() {
+        let commands = validate(&state(), vec![
+            Command::cast(Spell::Obliviate, 5),
+            Command::cast(Spell::Obliviate, 5),
+        ]);
+        assert_eq!(commands, vec![
+            Command::cast(Spell::Obliviate, 5),
+            Command::cast(Spell::Obliviate, 5),
+        ]);
+        let commands = validate(&state(), vec![
+            Command::cast(Spell::Flipendo, 4),
+            Command::wingardium(4, Vector2::new(0., 0.), 11),
+        ]);
+        assert_eq!(commands, vec![
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+        ]);
+    }
+
+    #[test]
+    fn fixed_cost_spells_are_paid_their_cost_whatever_their_power() {
+        let underpaid = |spell: Spell, target: i32| {
+            Command::Spell { kind: Effect::Spell(spell), target, dest: Vector2::new(0., 0.), power: 1 }
+        };
+        assert_eq!(underpaid(Spell::Flipendo, 4).magic(), Spell::Flipendo.cost());
+        let commands = validate(&state(), vec![underpaid(Spell::Flipendo, 4), underpaid(Spell::Petrificus, 2)]);
+        assert_eq!(commands, vec![
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+            Command::cast(Spell::Petrificus, 2),
+        ]);
+    }
+
+    #[test]
+    fn obliviate_only_acts_on_bludgers() {
+        let commands = validate(&state(), vec![
+            Command::cast(Spell::Obliviate, 2),
+            Command::cast(Spell::Obliviate, 4),
+        ]);
+        assert_eq!(commands, vec![
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+        ]);
+    }
+
+    #[test]
+    fn missing_command_is_filled_in() {
+        let commands = validate(&state(), vec![Command::Move(Vector2::new(3000., 2250.), 100)]);
+        assert_eq!(commands, vec![
+            Command::Move(Vector2::new(3000., 2250.), 100),
+            Command::Move(Vector2::new(5000., 3750.), MAX_THRUST),
+        ]);
+    }
+
+    #[test]
+    fn coordinates_are_rounded() {
+        assert_eq!(Command::Move(Vector2::new(1.6, -2.6), 100).line(), "MOVE 2 -3 100 MOVING");
+    }
+}
@@ -0,0 +1,135 @@
+// Wizards, snaffles and bludgers as received from the referee
+use crate::{Collider, GRAB_COOLDOWN};
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum EntityType {
+    Snaffle,
+    Wizard,
+    Opponent,
+    Bludger,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
+pub struct WizardState {
+    // Id of the snaffle carried
+    pub holding: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
+pub struct SnaffleState {
+    // Id of the carrying wizard
+    pub held_by: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
+pub struct BludgerState {
+    // Last wizard the bludger hit, the one it leaves alone until it hits another
+    pub last_victim: Option<i32>,
+}
+
+// What the protocol state column says, depending on the kind of entity
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum KindState {
+    Wizard(WizardState),
+    Snaffle(SnaffleState),
+    Bludger(BludgerState),
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Entity {
+    pub id: i32,
+    pub entity_type: EntityType,
+    pub collider: Collider,
+    pub kind: KindState,
+    pub target: Option<i32>,
+    // Turns left, this one included, before a wizard may grab a snaffle again
+    pub grab_cooldown: i32,
+}
+
+impl Entity {
+    // Carriers start unlinked from their snaffle, the state only flags both of them
+    pub fn new(id: i32, entity_type: EntityType, collider: Collider, state: i32) -> Entity {
+        let kind = match entity_type {
+            EntityType::Wizard | EntityType::Opponent => KindState::Wizard(WizardState::default()),
+            EntityType::Snaffle => KindState::Snaffle(SnaffleState::default()),
+            EntityType::Bludger => KindState::Bludger(BludgerState { last_victim: victim(state) }),
+        };
+        Entity { id, entity_type, collider, kind, target: None, grab_cooldown: 0 }
+    }
+    // A carrier and its snaffle come flagged with a state of 1, the link between them is left to the
+    // caller once every entity is updated. Bludgers get the id of their last victim or -1.
+    pub fn update(&mut self, x: i32, y: i32, vx: i32, vy: i32, state: i32) {
+        self.collider.pos.x = x as f32;
+        self.collider.pos.y = y as f32;
+        self.collider.vel.x = vx as f32;
+        self.collider.vel.y = vy as f32;
+        let flagged = state == 1;
+        match &mut self.kind {
+            KindState::Wizard(wizard) => {
+                // A wizard only lets go of a snaffle by throwing it, a turn of the cooldown has passed since
+                if wizard.holding.is_some() && !flagged {
+                    self.grab_cooldown = GRAB_COOLDOWN - 1;
+                } else if self.grab_cooldown > 0 {
+                    self.grab_cooldown -= 1;
+                }
+                if !flagged {
+                    wizard.holding = None;
+                }
+            }
+            KindState::Snaffle(snaffle) => if !flagged {
+                snaffle.held_by = None;
+            },
+            KindState::Bludger(bludger) => bludger.last_victim = victim(state),
+        }
+    }
+    // For wizards whether they carry a snaffle, for snaffles whether they are carried
+    pub fn has_snaffle(&self) -> bool {
+        self.holding().is_some() || self.held_by().is_some()
+    }
+    pub fn holding(&self) -> Option<i32> {
+        match self.kind {
+            KindState::Wizard(wizard) => wizard.holding,
+            _ => None,
+        }
+    }
+    pub fn held_by(&self) -> Option<i32> {
+        match self.kind {
+            KindState::Snaffle(snaffle) => snaffle.held_by,
+            _ => None,
+        }
+    }
+    pub fn last_victim(&self) -> Option<i32> {
+        match self.kind {
+            KindState::Bludger(bludger) => bludger.last_victim,
+            _ => None,
+        }
+    }
+    pub fn set_holding(&mut self, snaffle: Option<i32>) {
+        if let KindState::Wizard(wizard) = &mut self.kind {
+            wizard.holding = snaffle;
+        }
+    }
+    pub fn set_held_by(&mut self, wizard: Option<i32>) {
+        if let KindState::Snaffle(snaffle) = &mut self.kind {
+            snaffle.held_by = wizard;
+        }
+    }
+    pub fn set_last_victim(&mut self, wizard: Option<i32>) {
+        if let KindState::Bludger(bludger) = &mut self.kind {
+            bludger.last_victim = wizard;
+        }
+    }
+    pub fn is_wizard(&self) -> bool {
+        self.entity_type == EntityType::Wizard || self.entity_type == EntityType::Opponent
+    }
+    pub fn can_grab(&self) -> bool {
+        self.is_wizard() && !self.has_snaffle() && self.grab_cooldown == 0
+    }
+    pub fn set_target(&mut self, target: Option<i32>) {
+        self.target = target;
+    }
+}
+
+pub fn victim(state: i32) -> Option<i32> {
+    if state >= 0 { Some(state) } else { None }
+}
@@ -0,0 +1,130 @@
+// How good a position is for us: a weighted sum of features, higher is better
+use crate::physics::Simulation;
+use crate::{Entity, State, MAX_MAGIC};
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub struct Weights {
+    // Per goal of lead
+    pub goal: f32,
+    // Per unit a snaffle is closer to the target goal than to ours
+    pub snaffle_progress: f32,
+    // Per snaffle carried by us, minus the ones the opponent carries
+    pub possession: f32,
+    // Per unit between a wizard of ours and the loose snaffle closest to it
+    pub chase: f32,
+    // Per point of magic we have over the opponent
+    pub magic: f32,
+    // Per unit between our closest wizard and the guard position in front of our goal
+    pub positioning: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights { goal: 20000.0, snaffle_progress: 1.0, possession: 500.0, chase: 0.5, magic: 10.0, positioning: 0.1 }
+    }
+}
+
+// Distances and magic levels the heuristic decisions switch on
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub struct Thresholds {
+    // A snaffle predicted this close to a point of a goal mouth is worth a Wingardium, or an Accio at ours
+    pub near_goal: f32,
+    // Snaffles and carriers this close to our goal are threats, and opponents this close to such a snaffle
+    pub threat_range: f32,
+    // Carriers this close to the target goal shoot behind it
+    pub shooting_range: f32,
+    // A wizard this close ahead of the carrier gets the pass
+    pub pass_range: f32,
+    // Loose snaffles this close get flipped towards the target goal
+    pub flipendo_range: f32,
+    // Snaffles this close get pulled back from our goal
+    pub accio_range: f32,
+    // Magic a Wingardium takes while a snaffle is near a goal
+    pub urgent_magic: i32,
+    // Magic past which a Wingardium is cast wherever the snaffles are
+    pub spare_magic: i32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Thresholds {
+        Thresholds {
+            near_goal: 2500.0,
+            threat_range: 4000.0,
+            shooting_range: 4000.0,
+            pass_range: 1500.0,
+            flipendo_range: 4000.0,
+            accio_range: 5000.0,
+            urgent_magic: 15,
+            spare_magic: MAX_MAGIC / 2,
+        }
+    }
+}
+
+// The position at the end of a simulation, goals scored during it included
+pub fn evaluate(simulation: &Simulation, weights: &Weights) -> f32 {
+    score(&simulation.state, simulation.scored, weights)
+}
+
+// A position given the goals each team scored on top of the state scores, indexed by team id
+pub fn score(state: &State, scored: [i32; 2], weights: &Weights) -> f32 {
+    let team = state.team_id as usize;
+    let lead = (state.my_score + scored[team] - state.opponent_score - scored[1 - team]) as f32;
+    let snaffles = state.snaffles();
+    let snaffle_progress: f32 = snaffles.iter().map(|s| {
+        s.collider.pos.distance(state.own_goal.center()) - s.collider.pos.distance(state.target_goal.center())
+    }).sum();
+    let carried = |wizards: Vec<Entity>| wizards.iter().filter(|w| w.has_snaffle()).count() as f32;
+    let possession = carried(state.wizards()) - carried(state.opponents());
+    let chase: f32 = state.wizards().iter().filter(|w| !w.has_snaffle()).map(|w| {
+        snaffles.iter().filter(|s| !s.has_snaffle())
+                .map(|s| w.collider.pos.distance(s.collider.pos))
+                .min_by(|a, b| a.total_cmp(b))
+                .unwrap_or(0.0)
+    }).sum();
+    let magic = (state.magic - state.opponent_magic) as f32;
+    let guard = state.guard_position();
+    let positioning = state.wizards().iter()
+                           .map(|w| w.collider.pos.distance(guard))
+                           .min_by(|a, b| a.total_cmp(b))
+                           .unwrap_or(0.0);
+    weights.goal * lead + weights.snaffle_progress * snaffle_progress + weights.possession * possession -
+        weights.chase * chase + weights.magic * magic - weights.positioning * positioning
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::protocol::{EntityInput, TurnInput};
+    use crate::EntityType;
+
+    // Team 0 with a wizard at the snaffle and the opponents back at their goal
+    fn state(my_score: i32, holding: bool, snaffle_x: i32) -> State {
+        let entity = |id, entity_type, x, y, state| EntityInput { id, entity_type, x, y, vx: 0, vy: 0, state };
+        let input = TurnInput {
+            my_score, my_magic: 0, opponent_score: 0, opponent_magic: 0, received: Instant::now(),
+            entities: vec![
+                entity(0, EntityType::Wizard, snaffle_x, 3750, holding as i32),
+                entity(1, EntityType::Wizard, 1000, 5250, 0),
+                entity(2, EntityType::Opponent, 15000, 2250, 0),
+                entity(3, EntityType::Opponent, 15000, 5250, 0),
+                entity(4, EntityType::Snaffle, snaffle_x, 3750, holding as i32),
+            ],
+        };
+        let mut state = State::new(0);
+        state.update(&input, true);
+        state
+    }
+
+    #[test]
+    fn score_rises_with_lead_possession_and_progress() {
+        let weights = Weights::default();
+        let base = score(&state(0, false, 8000), [0, 0], &weights);
+        assert!(score(&state(1, false, 8000), [0, 0], &weights) > base);
+        assert!(score(&state(0, false, 8000), [1, 0], &weights) > base);
+        assert!(score(&state(0, false, 8000), [0, 1], &weights) < base);
+        assert!(score(&state(0, true, 8000), [0, 0], &weights) > base);
+        assert!(score(&state(0, false, 10000), [0, 0], &weights) > base);
+    }
+}
@@ -0,0 +1,190 @@
+// Rolling horizon evolution of per-wizard action sequences, scored by forward simulation
+use std::f32::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::eval::{self, Weights};
+use crate::physics::Simulation;
+use crate::spells::Spell;
+use crate::{Entity, EntityType, State, Vector2, MAX_POWER, MAX_THRUST};
+
+static DEPTH: usize = 4;
+static POPULATION: usize = 8;
+static MAX_GENERATIONS: usize = 100_000;
+static MUTATION_RATE: f32 = 0.3;
+static SPELL_RATE: f32 = 0.05;
+static DISCOUNT: f32 = 0.9;
+static SPELLS: [Spell; 4] = [Spell::Obliviate, Spell::Petrificus, Spell::Accio, Spell::Flipendo];
+
+// One turn of one wizard: a heading and a fraction of the max thrust or power, possibly a spell instead
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+struct Gene {
+    angle: f32,
+    power: f32,
+    spell: Option<(Spell, usize)>,
+}
+
+impl Gene {
+    fn random(rng: &mut StdRng) -> Gene {
+        let spell = if rng.gen::<f32>() < SPELL_RATE {
+            Some((SPELLS[rng.gen_range(0, SPELLS.len())], rng.gen_range(0, 16)))
+        } else {
+            None
+        };
+        Gene { angle: rng.gen_range(0.0, 2.0 * PI), power: rng.gen::<f32>(), spell }
+    }
+    fn mutate(&mut self, rng: &mut StdRng) {
+        match rng.gen_range(0, 3) {
+            0 => self.angle = (self.angle + rng.gen_range(-PI / 4.0, PI / 4.0)).rem_euclid(2.0 * PI),
+            1 => self.power = (self.power + rng.gen_range(-0.3, 0.3)).clamp(0.0, 1.0),
+            _ => *self = Gene::random(rng),
+        }
+    }
+}
+
+// What a gene resolves to for a wizard in a given state
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum GeneAction {
+    Move(Vector2, i32),
+    Throw(Vector2, i32),
+    Cast(Spell, i32),
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+struct Genome {
+    // DEPTH turns of genes for each of our two wizards
+    turns: Vec<[Gene; 2]>,
+    score: f32,
+}
+
+impl Genome {
+    fn random(rng: &mut StdRng) -> Genome {
+        Genome {
+            turns: (0..DEPTH).map(|_| [Gene::random(rng), Gene::random(rng)]).collect(),
+            score: f32::MIN,
+        }
+    }
+    fn crossover(&self, other: &Genome, rng: &mut StdRng) -> Genome {
+        let turns = self.turns.iter().zip(other.turns.iter()).map(|(a, b)| {
+            [
+                if rng.gen::<bool>() { a[0].clone() } else { b[0].clone() },
+                if rng.gen::<bool>() { a[1].clone() } else { b[1].clone() },
+            ]
+        }).collect();
+        Genome { turns, score: f32::MIN }
+    }
+    fn mutate(&mut self, rng: &mut StdRng) {
+        for turn in self.turns.iter_mut() {
+            for gene in turn.iter_mut() {
+                if rng.gen::<f32>() < MUTATION_RATE {
+                    gene.mutate(rng);
+                }
+            }
+        }
+    }
+    // Drops the turn that was just played and appends a fresh one at the horizon
+    fn shift(&mut self, rng: &mut StdRng) {
+        self.turns.remove(0);
+        self.turns.push([Gene::random(rng), Gene::random(rng)]);
+        self.score = f32::MIN;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneticSearch {
+    rng: StdRng,
+    best: Option<Genome>,
+    pub weights: Weights,
+}
+
+impl GeneticSearch {
+    pub fn new(seed: u64) -> GeneticSearch {
+        GeneticSearch { rng: StdRng::seed_from_u64(seed), best: None, weights: Weights::default() }
+    }
+    // Evolves the population and returns the first turn actions of the best genome, one per wizard
+    pub fn plan(&mut self, state: &State) -> Vec<GeneAction> {
+        let mut population = vec![];
+        if let Some(mut best) = self.best.take() {
+            best.shift(&mut self.rng);
+            population.push(best);
+        }
+        while population.len() < POPULATION {
+            population.push(Genome::random(&mut self.rng));
+        }
+        for genome in population.iter_mut() {
+            genome.score = evaluate_genome(state, genome, &self.weights);
+        }
+        for _ in 0..MAX_GENERATIONS {
+            if state.budget.is_exhausted() {
+                break;
+            }
+            population.sort_by(|a, b| b.score.total_cmp(&a.score));
+            let elite = population[0].clone();
+            let mut next = vec![elite];
+            while next.len() < POPULATION {
+                let a = self.tournament(&population);
+                let b = self.tournament(&population);
+                let mut child = population[a].crossover(&population[b], &mut self.rng);
+                child.mutate(&mut self.rng);
+                child.score = evaluate_genome(state, &child, &self.weights);
+                next.push(child);
+            }
+            population = next;
+        }
+        population.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let best = population.swap_remove(0);
+        let wizards = state.wizards();
+        let actions = wizards.iter().zip(best.turns[0].iter())
+                             .map(|(wizard, gene)| resolve_gene(state, wizard, gene))
+                             .collect();
+        self.best = Some(best);
+        actions
+    }
+    fn tournament(&mut self, population: &[Genome]) -> usize {
+        let a = self.rng.gen_range(0, population.len());
+        let b = self.rng.gen_range(0, population.len());
+        if population[a].score > population[b].score { a } else { b }
+    }
+}
+
+fn resolve_gene(state: &State, wizard: &Entity, gene: &Gene) -> GeneAction {
+    let pos = wizard.collider.pos;
+    let dest = pos.add(Vector2::new(gene.angle.cos(), gene.angle.sin()).mul_num(1000.0));
+    if wizard.has_snaffle() {
+        return GeneAction::Throw(dest, (gene.power * MAX_POWER as f32).round() as i32);
+    }
+    if let Some((spell, index)) = gene.spell {
+        // Spells target anything but our own wizards, and only what they can act on
+        let targets: Vec<&Entity> = state.entities.iter()
+                                         .filter(|e| e.entity_type != EntityType::Wizard && spell.can_target(&e.entity_type))
+                                         .collect();
+        if state.magic >= spell.cost() && !targets.is_empty() {
+            return GeneAction::Cast(spell, targets[index % targets.len()].id);
+        }
+    }
+    GeneAction::Move(dest, (gene.power * MAX_THRUST as f32).round() as i32)
+}
+
+fn evaluate_genome(state: &State, genome: &Genome, weights: &Weights) -> f32 {
+    let mut simulation = Simulation::new(state);
+    let mut score = 0.0;
+    let mut weight = 1.0;
+    for turn in genome.turns.iter() {
+        let wizards = simulation.state.wizards();
+        for (wizard, gene) in wizards.iter().zip(turn.iter()) {
+            match resolve_gene(&simulation.state, wizard, gene) {
+                GeneAction::Move(dest, thrust) => simulation.move_wizard(wizard.id, dest, thrust),
+                GeneAction::Throw(dest, power) => simulation.throw(wizard.id, dest, power),
+                GeneAction::Cast(spell, target) => simulation.cast(wizard.id, spell, target),
+            }
+        }
+        // Opponents play as the heuristics predict them rather than drift
+        state.opponent_model.steer(&mut simulation);
+        simulation.step();
+        score += weight * eval::evaluate(&simulation, weights);
+        weight *= DISCOUNT;
+    }
+    // A degenerate simulation ranks with the unscored genomes rather than above every other one
+    if score.is_nan() { f32::MIN } else { score }
+}
@@ -0,0 +1,67 @@
+// Vectors and circular bodies the whole game is made of
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub struct Vector2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector2 {
+    pub fn new(x: f32, y: f32) -> Vector2 {
+        Vector2 { x, y }
+    }
+    pub fn add(&self, v2: Vector2) -> Vector2 {
+        Vector2::new(self.x + v2.x, self.y + v2.y)
+    }
+    pub fn negate(&self) -> Vector2 {
+        Vector2::new(-self.x, -self.y)
+    }
+    pub fn heading(&self, target: Vector2) -> Vector2 { Vector2::new(target.x - self.x, target.y - self.y) }
+    pub fn direction(&self, target: Vector2) -> Vector2 {
+        let heading = self.heading(target);
+        let dist = self.distance(target);
+        Vector2::new(heading.x / dist, heading.y / dist)
+    }
+    pub fn mul_num(&self, num: f32) -> Vector2 {
+        Vector2::new(self.x * num, self.y * num)
+    }
+    pub fn distance(&self, v2: Vector2) -> f32 {
+        ((self.x - v2.x).powi(2) +
+            (self.y - v2.y).powi(2)).sqrt()
+    }
+    pub fn sub(&self, v2: Vector2) -> Vector2 {
+        Vector2::new(self.x - v2.x, self.y - v2.y)
+    }
+    pub fn dot(&self, v2: Vector2) -> f32 {
+        self.x * v2.x + self.y * v2.y
+    }
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+    // Counterclockwise by an angle in radians
+    pub fn rotate(&self, angle: f32) -> Vector2 {
+        let (sin, cos) = angle.sin_cos();
+        Vector2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+    pub fn round(&self) -> Vector2 {
+        Vector2::new(self.x.round(), self.y.round())
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Collider {
+    pub pos: Vector2,
+    pub vel: Vector2,
+    pub friction: f32,
+    pub mass: f32,
+    pub radius: f32,
+}
+
+impl Collider {
+    pub fn new(pos: Vector2, vel: Vector2, friction: f32, mass: f32, radius: f32) -> Collider {
+        Collider { pos, vel, friction, mass, radius }
+    }
+    pub fn collides(&self, other: &Collider) -> bool {
+        self.pos.distance(other.pos) < self.radius + other.radius
+    }
+}
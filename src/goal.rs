@@ -0,0 +1,56 @@
+// Goal mouths between two poles at either end of the map
+use crate::physics::{GOAL_BOTTOM, GOAL_TOP};
+use crate::{Collider, Vector2, POLE_RADIUS};
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Goal {
+    pub pole_top: Collider,
+    pub pole_bottom: Collider,
+}
+
+impl Goal {
+    pub fn new(team_id: i32) -> Goal {
+        if team_id == 0 {
+            Goal {
+                pole_top: Collider::new(
+                    Vector2::new(16000.0, GOAL_TOP),
+                    Vector2::new(0.0, 0.0), 0., 0.0, POLE_RADIUS, ),
+                pole_bottom: Collider::new(
+                    Vector2::new(16000.0, GOAL_BOTTOM),
+                    Vector2::new(0.0, 0.0), 0., 0.0, POLE_RADIUS, ),
+            }
+        } else {
+            Goal {
+                pole_top: Collider::new(
+                    Vector2::new(0.0, GOAL_TOP),
+                    Vector2::new(0.0, 0.0), 0., 0.0, POLE_RADIUS, ),
+                pole_bottom: Collider::new(
+                    Vector2::new(0.0, GOAL_BOTTOM),
+                    Vector2::new(0.0, 0.0), 0., 0.0, POLE_RADIUS, ),
+            }
+        }
+    }
+    pub fn destination_is_close(&self, destination: Vector2, close_to_limit: f32) -> bool {
+        self.points_inside_goal(10).iter().any(|&point| {
+            let dist_from_point = destination.distance(point);
+            dist_from_point < close_to_limit
+        })
+    }
+    pub fn points_inside_goal(&self, num: usize) -> Vec<Vector2> {
+        let div = num as f32;
+        let mut points = vec![];
+        let dist = self.pole_top.pos.distance(self.pole_bottom.pos) - self.pole_top.radius * 2.0;
+        for i in 0..(num - 1) {
+            points.push(Vector2::new(
+                self.pole_bottom.pos.x,
+                self.pole_top.pos.y + self.pole_top.radius + 200.0 + i as f32 * (dist / div),
+            ))
+        }
+        points
+    }
+    pub fn center(&self) -> Vector2 { Vector2::new(self.pole_bottom.pos.x, 3750.0) }
+    pub fn behind_goal(&self) -> Vector2 {
+        let center = self.center();
+        Vector2::new(center.x + if center.x == 0. { -2000. } else { 2000. }, center.y)
+    }
+}
@@ -0,0 +1,124 @@
+// How soon and in which direction a wizard driving at full thrust can reach a moving body
+use crate::physics::{GOAL_BOTTOM, GOAL_TOP};
+use crate::{Collider, Entity, EntityType, Vector2, HEIGHT, MAX_THRUST, WIDTH};
+
+// Turns looked ahead before a body is considered out of reach
+pub static HORIZON: i32 = 30;
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Intercept {
+    // Turns until contact, the current one being the first
+    pub turns: i32,
+    // Where the target is when touched
+    pub point: Vector2,
+    // Point to move towards for the thrust to point the right way
+    pub aim: Vector2,
+}
+
+// Where a body coasting under friction alone is after some turns, walls ignored
+pub fn coast(body: &Collider, turns: i32) -> Vector2 {
+    let travelled = (0..turns).map(|k| body.friction.powi(k)).sum::<f32>();
+    body.pos.add(body.vel.mul_num(travelled))
+}
+
+// Positions of a body left to itself at the end of each of the next turns, bouncing off the walls.
+// Snaffles crossing a goal line between the poles leave the map and end the path early.
+pub fn trajectory(body: &Entity, turns: i32) -> Vec<Vector2> {
+    let radius = body.collider.radius;
+    let (mut pos, mut vel) = (body.collider.pos, body.collider.vel);
+    let mut path = vec![];
+    for _ in 0..turns {
+        pos = pos.add(vel);
+        if pos.x < radius || pos.x > (WIDTH - 1) as f32 - radius {
+            if body.entity_type == EntityType::Snaffle && pos.y > GOAL_TOP && pos.y < GOAL_BOTTOM {
+                break;
+            }
+            pos.x = if pos.x < radius { 2. * radius - pos.x } else { 2. * ((WIDTH - 1) as f32 - radius) - pos.x };
+            vel.x = -vel.x;
+        }
+        if pos.y < radius || pos.y > (HEIGHT - 1) as f32 - radius {
+            pos.y = if pos.y < radius { 2. * radius - pos.y } else { 2. * ((HEIGHT - 1) as f32 - radius) - pos.y };
+            vel.y = -vel.y;
+        }
+        path.push(pos);
+        vel = vel.mul_num(body.collider.friction);
+    }
+    path
+}
+
+// Distance full thrust in a fixed direction adds to coasting over some turns
+fn reach(wizard: &Collider, turns: i32) -> f32 {
+    let mut speed = 0.0;
+    let mut distance = 0.0;
+    for _ in 0..turns {
+        speed += MAX_THRUST as f32 / wizard.mass;
+        distance += speed;
+        speed *= wizard.friction;
+    }
+    distance
+}
+
+// Earliest turn a wizard can touch a target following the path, reached when its own drift plus
+// what the thrust adds in a straight line covers the gap
+pub fn solve_path(wizard: &Collider, target_radius: f32, path: &[Vector2]) -> Option<Intercept> {
+    path.iter().zip(1..).find_map(|(&point, turns)| {
+        let gap = point.sub(coast(wizard, turns));
+        if gap.length() > reach(wizard, turns) + wizard.radius + target_radius {
+            return None;
+        }
+        let aim = if gap.length() > 0.0 { wizard.pos.add(gap) } else { point };
+        Some(Intercept { turns, point, aim })
+    })
+}
+
+pub fn solve(wizard: &Collider, target: &Entity) -> Option<Intercept> {
+    solve_path(wizard, target.collider.radius, &trajectory(target, HORIZON))
+}
+
+// Turns to get next to a fixed point
+pub fn turns_to(wizard: &Collider, point: Vector2) -> Option<i32> {
+    solve_path(wizard, 0.0, &vec![point; HORIZON as usize]).map(|i| i.turns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS};
+    use crate::{WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+    fn body(entity_type: EntityType, pos: Vector2, vel: Vector2) -> Entity {
+        let (friction, mass, radius) = match entity_type {
+            EntityType::Snaffle => (SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+            EntityType::Bludger => (BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS),
+            _ => (WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+        };
+        Entity::new(4, entity_type, Collider::new(pos, vel, friction, mass, radius), 0)
+    }
+
+    #[test]
+    fn path_bounces_off_the_top_wall() {
+        let snaffle = body(EntityType::Snaffle, Vector2::new(8000., 300.), Vector2::new(0., -400.));
+        assert_eq!(trajectory(&snaffle, 2), vec![Vector2::new(8000., 400.), Vector2::new(8000., 700.)]);
+    }
+
+    #[test]
+    fn snaffle_path_stops_at_the_goal_mouth() {
+        let (pos, vel) = (Vector2::new(15300., 3750.), Vector2::new(300., 0.));
+        assert_eq!(trajectory(&body(EntityType::Snaffle, pos, vel), 5),
+                   vec![Vector2::new(15600., 3750.), Vector2::new(15825., 3750.)]);
+        // Anything else bounces off the goal line like off any wall
+        assert_eq!(trajectory(&body(EntityType::Bludger, pos, vel), 5).len(), 5);
+    }
+
+    #[test]
+    fn reachable_point_is_touched_once_thrust_covers_the_gap() {
+        let wizard = Collider::new(Vector2::new(3000., 3000.), Vector2::new(0., 0.), WIZARD_FRICTION, WIZARD_MASS,
+                                   WIZARD_RADIUS);
+        // Full thrust covers 150, 412.5 then 759.4 units, the wizard radius the rest
+        let point = Vector2::new(4100., 3000.);
+        assert_eq!(turns_to(&wizard, point), Some(3));
+        let intercept = solve_path(&wizard, 0.0, &[point; 5]).unwrap();
+        assert_eq!((intercept.turns, intercept.aim), (3, point));
+        assert_eq!(turns_to(&wizard, Vector2::new(4200., 3000.)), Some(4));
+    }
+}
@@ -0,0 +1,108 @@
+// Fantastic Bits bot: game model, referee physics and decision making
+//
+// Modules only refer to each other through `crate::` paths so the crate can be
+// inlined into the single source file CodinGame accepts.
+pub mod assignment;
+pub mod command;
+pub mod entity;
+pub mod eval;
+pub mod genetic;
+pub mod geometry;
+pub mod goal;
+pub mod intercept;
+pub mod mcts;
+pub mod opponent;
+pub mod physics;
+pub mod protocol;
+pub mod recording;
+pub mod shot;
+pub mod spell_tracker;
+pub mod spells;
+pub mod state;
+pub mod strategy;
+pub mod time_budget;
+
+pub use entity::{Entity, EntityType};
+pub use geometry::{Collider, Vector2};
+pub use goal::Goal;
+pub use state::{ActionType, Endgame, State, TargetStrategy};
+pub use strategy::Strategy;
+
+use command::Command;
+use protocol::{CommandSink, ParseError, TurnSource};
+
+pub static WIDTH: i32 = 16001;
+pub static HEIGHT: i32 = 7501;
+pub static MAX_THRUST: i32 = 150;
+pub static MAX_POWER: i32 = 500;
+pub static MAX_MAGIC: i32 = 100;
+pub static LOOKAHEAD: i32 = 3;
+pub static MAX_TURNS: i32 = 200;
+pub static WIZARDS_PER_TEAM: usize = 2;
+// Bodies of every kind: radius, mass and friction
+pub static WIZARD_RADIUS: f32 = 400.0;
+pub static WIZARD_MASS: f32 = 1.0;
+pub static WIZARD_FRICTION: f32 = 0.75;
+pub static SNAFFLE_RADIUS: f32 = 150.0;
+pub static SNAFFLE_MASS: f32 = 0.5;
+pub static SNAFFLE_FRICTION: f32 = 0.75;
+pub static BLUDGER_RADIUS: f32 = 200.0;
+pub static BLUDGER_MASS: f32 = 8.0;
+pub static BLUDGER_FRICTION: f32 = 0.9;
+pub static POLE_RADIUS: f32 = 300.0;
+// Turns a wizard cannot grab a snaffle for after throwing it, counting the turn of the throw
+pub static GRAB_COOLDOWN: i32 = 3;
+
+// Plays turns until the input ends, handing the commands of every turn to the sink
+pub fn play<S: TurnSource, C: CommandSink>(source: &mut S, strategy: &mut dyn Strategy, sink: &mut C)
+                                          -> Result<(), ParseError> {
+    let my_team_id = source.read_team_id()?;
+    let mut init = true;
+    let mut state = State::new(my_team_id);
+
+    // Every turn received gets its commands, a turn left unanswered would shift all the following ones
+    loop {
+        match source.read_turn() {
+            Ok(input) => {
+                state.update(&input, init);
+                init = false;
+            }
+            Err(ParseError::UnexpectedEof) => return Ok(()),
+            // Acting on the previous turn beats forfeiting on a single bad turn
+            Err(error) => {
+                eprintln!("Bad turn input: {}", error);
+                state.skip_turn();
+            }
+        }
+        let commands = if init {
+            // Nothing known to act on yet, the wizards stay put
+            vec![Command::Move(Vector2::new(0., 0.), 0).line(); WIZARDS_PER_TEAM]
+        } else {
+            state.act_turn(strategy)
+        };
+        state.budget.report();
+        sink.emit(commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use protocol::ProtocolReader;
+
+    #[test]
+    fn every_turn_gets_commands_even_a_bad_one() {
+        let turn = |wizard_y: &str| {
+            format!("0 0\n0 0\n4\n0 WIZARD 1000 {} 0 0 0\n1 WIZARD 1000 5250 0 0 0\n\
+                     2 OPPONENT_WIZARD 15000 5250 0 0 0\n3 SNAFFLE 8000 3750 0 0 0\n", wizard_y)
+        };
+        let input = format!("0\n{}{}{}", turn("bad"), turn("2250"), turn("oops"));
+        let mut emitted = vec![];
+        play(&mut ProtocolReader::new(Cursor::new(input)), &mut strategy::Heuristic,
+             &mut |commands: Vec<String>| emitted.push(commands)).unwrap();
+        assert_eq!(emitted.len(), 3);
+        assert!(emitted.iter().all(|commands| commands.len() == WIZARDS_PER_TEAM));
+    }
+}
@@ -0,0 +1,201 @@
+// Decoupled UCT over simultaneous turns of all four wizards
+//
+// Every wizard picks one of a few abstract actions (chase a snaffle, head in a
+// direction, throw at a part of the goal, cast) that are resolved against the
+// simulated state of the node, so the tree is open loop and replays its path from
+// the root each iteration. Each node keeps separate statistics per wizard and a
+// wizard selects its action from its own statistics only, which is what makes
+// the simultaneous moves tractable.
+use std::f32::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::eval::{self, Weights};
+use crate::genetic::GeneAction;
+use crate::physics::Simulation;
+use crate::spells::Spell;
+use crate::{Entity, EntityType, Goal, State, Vector2, MAX_POWER, MAX_THRUST};
+
+static DEPTH: usize = 4;
+static MAX_ITERATIONS: usize = 1_000_000;
+static MAX_NODES: usize = 100_000;
+static EXPLORATION: f32 = 0.7;
+// Evaluation difference from the root that maps to a value of about 0.88
+static VALUE_SCALE: f32 = 5000.0;
+static HEADINGS: usize = 8;
+// Chase closest, chase second closest, fall back to own goal, then the headings
+static MOVE_ACTIONS: usize = 3 + HEADINGS;
+// Our wizards can also cast Accio and Flipendo on the closest snaffle, opponent magic is unknown
+static OUR_ACTIONS: usize = MOVE_ACTIONS + 2;
+
+#[derive(Debug, Clone, Default, PartialOrd, PartialEq)]
+struct Arm {
+    visits: u32,
+    value: f32,
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+struct Node {
+    visits: u32,
+    // Statistics of every action for each wizard, our wizards first
+    arms: Vec<Vec<Arm>>,
+    // Child for every joint action tried from this node
+    children: Vec<(Vec<usize>, usize)>,
+}
+
+impl Node {
+    fn new(action_counts: &[usize]) -> Node {
+        Node { visits: 0, arms: action_counts.iter().map(|&n| vec![Arm::default(); n]).collect(), children: vec![] }
+    }
+    fn child(&self, joint: &[usize]) -> Option<usize> {
+        self.children.iter().find(|(j, _)| j.as_slice() == joint).map(|(_, child)| *child)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsSearch {
+    rng: StdRng,
+    pub weights: Weights,
+}
+
+impl MctsSearch {
+    pub fn new(seed: u64) -> MctsSearch {
+        MctsSearch { rng: StdRng::seed_from_u64(seed), weights: Weights::default() }
+    }
+    // Searches until the turn budget runs out and returns the most visited action of each of our wizards
+    pub fn plan(&mut self, state: &State) -> Vec<GeneAction> {
+        let players: Vec<Entity> = state.wizards().into_iter().chain(state.opponents()).collect();
+        let ours = state.wizards().len();
+        let action_counts: Vec<usize> = (0..players.len())
+            .map(|i| if i < ours { OUR_ACTIONS } else { MOVE_ACTIONS })
+            .collect();
+        let baseline = eval::evaluate(&Simulation::new(state), &self.weights);
+        let mut nodes = vec![Node::new(&action_counts)];
+        for _ in 0..MAX_ITERATIONS {
+            if state.budget.is_exhausted() {
+                break;
+            }
+            let mut simulation = Simulation::new(state);
+            let mut path = vec![];
+            let mut node = 0;
+            let mut expanded = false;
+            for _ in 0..DEPTH {
+                let joint: Vec<usize> = if expanded {
+                    action_counts.iter().map(|&n| self.rng.gen_range(0, n)).collect()
+                } else {
+                    (0..players.len()).map(|p| self.select(&nodes[node], p)).collect()
+                };
+                apply_joint(&mut simulation, &players, ours, &joint);
+                simulation.step();
+                if expanded {
+                    continue;
+                }
+                path.push((node, joint.clone()));
+                node = match nodes[node].child(&joint) {
+                    Some(child) => child,
+                    None => {
+                        expanded = true;
+                        if nodes.len() >= MAX_NODES {
+                            continue;
+                        }
+                        nodes.push(Node::new(&action_counts));
+                        let child = nodes.len() - 1;
+                        nodes[node].children.push((joint, child));
+                        child
+                    }
+                };
+            }
+            let value = 0.5 + 0.5 * ((eval::evaluate(&simulation, &self.weights) - baseline) / VALUE_SCALE).tanh();
+            // A degenerate simulation counts as neither a win nor a loss rather than poisoning the statistics
+            let value = if value.is_nan() { 0.5 } else { value };
+            for (visited, joint) in path {
+                let node = &mut nodes[visited];
+                node.visits += 1;
+                for (player, &action) in joint.iter().enumerate() {
+                    let arm = &mut node.arms[player][action];
+                    arm.visits += 1;
+                    arm.value += if player < ours { value } else { 1.0 - value };
+                }
+            }
+        }
+        let simulation = Simulation::new(state);
+        let root = &nodes[0];
+        players.iter().take(ours).enumerate().map(|(player, wizard)| {
+            let action = (0..root.arms[player].len()).max_by_key(|&a| root.arms[player][a].visits).unwrap_or(0);
+            resolve(&simulation, wizard.id, true, action)
+        }).collect()
+    }
+    // UCB1 on the statistics of one wizard, untried actions first in random order
+    fn select(&mut self, node: &Node, player: usize) -> usize {
+        let arms = &node.arms[player];
+        let untried: Vec<usize> = (0..arms.len()).filter(|&a| arms[a].visits == 0).collect();
+        if !untried.is_empty() {
+            return untried[self.rng.gen_range(0, untried.len())];
+        }
+        let log_visits = (node.visits.max(1) as f32).ln();
+        let ucb = |arm: &Arm| arm.value / arm.visits as f32 + EXPLORATION * (log_visits / arm.visits as f32).sqrt();
+        (0..arms.len()).max_by(|&a, &b| ucb(&arms[a]).total_cmp(&ucb(&arms[b]))).unwrap_or(0)
+    }
+}
+
+fn apply_joint(simulation: &mut Simulation, players: &[Entity], ours: usize, joint: &[usize]) {
+    for (player, (wizard, &action)) in players.iter().zip(joint.iter()).enumerate() {
+        match resolve(simulation, wizard.id, player < ours, action) {
+            GeneAction::Move(dest, thrust) => simulation.move_wizard(wizard.id, dest, thrust),
+            GeneAction::Throw(dest, power) => simulation.throw(wizard.id, dest, power),
+            GeneAction::Cast(spell, target) => simulation.cast(wizard.id, spell, target),
+        }
+    }
+}
+
+// Concrete action of an abstract action index for a wizard in the simulated state
+fn resolve(simulation: &Simulation, id: i32, ours: bool, action: usize) -> GeneAction {
+    let state = &simulation.state;
+    let wizard = match state.entities.iter().find(|e| e.id == id) {
+        Some(wizard) => wizard,
+        None => return GeneAction::Move(Vector2::new(0.0, 0.0), 0),
+    };
+    let pos = wizard.collider.pos;
+    let (attacked, defended) = if ours { (&state.target_goal, &state.own_goal) } else { (&state.own_goal, &state.target_goal) };
+    let heading = |index: usize| {
+        let angle = 2.0 * PI * index as f32 / HEADINGS as f32;
+        pos.add(Vector2::new(angle.cos(), angle.sin()).mul_num(1000.0))
+    };
+    if wizard.has_snaffle() {
+        let dest = match action {
+            0..=2 => goal_aim(attacked, action),
+            a if a < MOVE_ACTIONS => heading(a - 3),
+            _ => goal_aim(attacked, 0),
+        };
+        return GeneAction::Throw(dest, MAX_POWER);
+    }
+    let mut loose: Vec<&Entity> = state.entities.iter()
+                                       .filter(|e| e.entity_type == EntityType::Snaffle && !e.has_snaffle())
+                                       .collect();
+    loose.sort_by(|a, b| pos.distance(a.collider.pos).total_cmp(&pos.distance(b.collider.pos)));
+    let closest = loose.first().map(|s| s.id);
+    let chase = |nth: usize| loose.get(nth).or_else(|| loose.first()).map_or(defended.center(), |s| s.collider.pos);
+    match action {
+        0 | 1 => GeneAction::Move(chase(action), MAX_THRUST),
+        2 => GeneAction::Move(defended.center(), MAX_THRUST),
+        a if a < MOVE_ACTIONS => GeneAction::Move(heading(a - 3), MAX_THRUST),
+        a => {
+            let spell = if a == MOVE_ACTIONS { Spell::Accio } else { Spell::Flipendo };
+            match closest {
+                Some(target) if state.magic >= spell.cost() => GeneAction::Cast(spell, target),
+                _ => GeneAction::Move(chase(0), MAX_THRUST),
+            }
+        }
+    }
+}
+
+// Center, upper and lower part of the goal mouth
+fn goal_aim(goal: &Goal, part: usize) -> Vector2 {
+    let center = goal.center();
+    match part {
+        1 => Vector2::new(center.x, goal.pole_top.pos.y + 600.0),
+        2 => Vector2::new(center.x, goal.pole_bottom.pos.y - 600.0),
+        _ => center,
+    }
+}
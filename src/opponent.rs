@@ -0,0 +1,172 @@
+// What the opponent wizards are likely to do next, learned from how they accelerated in previous turns
+use crate::physics::Simulation;
+use crate::{Entity, EntityType, Goal, Vector2, MAX_POWER, MAX_THRUST};
+
+// Weight kept from older observations on every new one
+static DECAY: f32 = 0.8;
+// Accelerations below this are taken as not moving at all
+static MIN_THRUST: f32 = 30.0;
+// Cosine between the thrust and a target direction needed to count as heading there
+static ALIGNMENT: f32 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum Behaviour {
+    ChaseSnaffle,
+    Defend,
+    Drift,
+}
+
+static BEHAVIOURS: [Behaviour; 3] = [Behaviour::ChaseSnaffle, Behaviour::Defend, Behaviour::Drift];
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+struct Tracked {
+    id: i32,
+    // Decayed number of turns each behaviour explained the observed thrust, indexed like BEHAVIOURS
+    weights: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum OpponentAction {
+    Move(Vector2, i32),
+    Throw(Vector2, i32),
+}
+
+#[derive(Debug, Clone, Default, PartialOrd, PartialEq)]
+pub struct OpponentModel {
+    tracked: Vec<Tracked>,
+}
+
+impl OpponentModel {
+    // Compares the thrust each opponent applied last turn with where each behaviour would have steered it,
+    // the defended goal is the opponent's own
+    pub fn observe(&mut self, previous: &[Entity], current: &[Entity], defended: &Goal) {
+        for opponent in current.iter().filter(|e| e.entity_type == EntityType::Opponent) {
+            let before = match previous.iter().find(|e| e.id == opponent.id) {
+                Some(before) => before,
+                None => continue,
+            };
+            // Carriers throw instead of moving, their own velocity tells nothing
+            if before.has_snaffle() {
+                continue;
+            }
+            let collider = &opponent.collider;
+            let thrust = collider.vel.mul_num(1.0 / collider.friction).sub(before.collider.vel).mul_num(collider.mass);
+            let explained = if thrust.length() < MIN_THRUST {
+                Some(Behaviour::Drift)
+            } else {
+                let heading = thrust.mul_num(1.0 / thrust.length());
+                let aims_at = |target: Vector2| heading.dot(before.collider.pos.direction(target)) > ALIGNMENT;
+                let chased = closest_loose_snaffle(previous, before.collider.pos);
+                if chased.is_some_and(aims_at) {
+                    Some(Behaviour::ChaseSnaffle)
+                } else if aims_at(defended.center()) {
+                    Some(Behaviour::Defend)
+                } else {
+                    None
+                }
+            };
+            let tracked = match self.tracked.iter().position(|t| t.id == opponent.id) {
+                Some(i) => &mut self.tracked[i],
+                None => {
+                    self.tracked.push(Tracked { id: opponent.id, weights: [0.0; 3] });
+                    self.tracked.last_mut().unwrap()
+                }
+            };
+            for (weight, behaviour) in tracked.weights.iter_mut().zip(BEHAVIOURS.iter()) {
+                *weight = *weight * DECAY + if explained == Some(*behaviour) { 1.0 } else { 0.0 };
+            }
+        }
+    }
+    // Most likely behaviour so far, opponents are assumed to chase snaffles until seen doing otherwise
+    pub fn behaviour(&self, id: i32) -> Behaviour {
+        self.tracked.iter().find(|t| t.id == id)
+            .and_then(|t| {
+                (0..BEHAVIOURS.len())
+                    .filter(|&b| t.weights[b] > 0.0)
+                    .max_by(|&a, &b| t.weights[a].total_cmp(&t.weights[b]))
+            })
+            .map_or(Behaviour::ChaseSnaffle, |b| BEHAVIOURS[b])
+    }
+    // Next action of an opponent in a state, carriers are expected to throw at the goal they attack
+    pub fn predict(&self, opponent: &Entity, entities: &[Entity], attacked: &Goal, defended: &Goal)
+                   -> Option<OpponentAction> {
+        if opponent.has_snaffle() {
+            return Some(OpponentAction::Throw(attacked.center(), MAX_POWER));
+        }
+        match self.behaviour(opponent.id) {
+            Behaviour::ChaseSnaffle => closest_loose_snaffle(entities, opponent.collider.pos)
+                .map(|snaffle| OpponentAction::Move(snaffle, MAX_THRUST)),
+            Behaviour::Defend => Some(OpponentAction::Move(defended.center(), MAX_THRUST)),
+            Behaviour::Drift => None,
+        }
+    }
+    // Applies the predicted actions of all opponents for the next simulation step
+    pub fn steer(&self, simulation: &mut Simulation) {
+        let state = &simulation.state;
+        let actions: Vec<(i32, OpponentAction)> = state.opponents().iter().filter_map(|opponent| {
+            self.predict(opponent, &state.entities, &state.own_goal, &state.target_goal)
+                .map(|action| (opponent.id, action))
+        }).collect();
+        for (id, action) in actions {
+            match action {
+                OpponentAction::Move(dest, thrust) => simulation.move_wizard(id, dest, thrust),
+                OpponentAction::Throw(dest, power) => simulation.throw(id, dest, power),
+            }
+        }
+    }
+}
+
+fn closest_loose_snaffle(entities: &[Entity], pos: Vector2) -> Option<Vector2> {
+    entities.iter()
+            .filter(|e| e.entity_type == EntityType::Snaffle && !e.has_snaffle())
+            .map(|e| e.collider.pos)
+            .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collider, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS, WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+    fn body(id: i32, entity_type: EntityType, pos: Vector2, vel: Vector2) -> Entity {
+        let (friction, mass, radius) = match entity_type {
+            EntityType::Snaffle => (SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+            _ => (WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+        };
+        Entity::new(id, entity_type, Collider::new(pos, vel, friction, mass, radius), 0)
+    }
+
+    // Opponent 2 at rest at (8000, 3750) with a snaffle straight above it, then after a full thrust
+    // towards the given point
+    fn turn(towards: Vector2) -> (Vec<Entity>, Vec<Entity>) {
+        let pos = Vector2::new(8000., 3750.);
+        let snaffle = body(5, EntityType::Snaffle, Vector2::new(8000., 1000.), Vector2::new(0., 0.));
+        let previous = vec![body(2, EntityType::Opponent, pos, Vector2::new(0., 0.)), snaffle.clone()];
+        let vel = pos.direction(towards).mul_num(MAX_THRUST as f32 * WIZARD_FRICTION);
+        let current = vec![body(2, EntityType::Opponent, pos.add(vel), vel), snaffle];
+        (previous, current)
+    }
+
+    #[test]
+    fn thrust_at_the_nearest_snaffle_is_chasing() {
+        let goal = Goal::new(1);
+        let (previous, current) = turn(Vector2::new(8000., 1000.));
+        let mut model = OpponentModel::default();
+        model.observe(&previous, &current, &goal);
+        assert_eq!(model.behaviour(2), Behaviour::ChaseSnaffle);
+    }
+
+    #[test]
+    fn thrust_at_the_defended_goal_is_defending() {
+        let goal = Goal::new(1);
+        let (previous, current) = turn(goal.center());
+        let mut model = OpponentModel::default();
+        model.observe(&previous, &current, &goal);
+        assert_eq!(model.behaviour(2), Behaviour::Defend);
+        // A single turn chasing does not outweigh two turns defending
+        model.observe(&previous, &current, &goal);
+        let (previous, current) = turn(Vector2::new(8000., 1000.));
+        model.observe(&previous, &current, &goal);
+        assert_eq!(model.behaviour(2), Behaviour::Defend);
+    }
+}
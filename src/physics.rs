@@ -0,0 +1,486 @@
+// Turn-by-turn simulation of the Fantastic Bits referee physics
+use crate::{Collider, Entity, EntityType, State, Vector2, GRAB_COOLDOWN, HEIGHT, MAX_MAGIC, MAX_POWER, MAX_THRUST, WIDTH};
+use crate::spell_tracker;
+use crate::spells::Spell;
+
+static MIN_IMPULSE: f32 = 100.0;
+static BLUDGER_THRUST: f32 = 1000.0;
+// Goal mouth a snaffle centre crosses the goal line through, between the pole centres
+pub static GOAL_TOP: f32 = 1750.0;
+pub static GOAL_BOTTOM: f32 = 5750.0;
+static MAX_COLLISIONS: usize = 100;
+// Force of a Wingardium per point of magic
+pub static WINGARDIUM_FORCE: f32 = 15.0;
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+struct Effect {
+    spell: Spell,
+    caster: i32,
+    caster_type: EntityType,
+    target: i32,
+    turns_left: i32,
+}
+
+// Moves and throws of the turn, carried out once the spells have acted
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+enum Order {
+    Move(i32, Vector2, f32),
+    Throw(i32, Vector2, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+enum Collision {
+    Bounce(usize, usize),
+    Grab(usize, usize),
+    Pole(usize, usize),
+    VerticalWall(usize),
+    HorizontalWall(usize),
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Simulation {
+    pub state: State,
+    // Goals scored during the simulation, indexed by team id
+    pub scored: [i32; 2],
+    poles: Vec<Collider>,
+    effects: Vec<Effect>,
+    orders: Vec<Order>,
+}
+
+impl Simulation {
+    pub fn new(state: &State) -> Simulation {
+        let mut state = state.clone();
+        state.predicted = vec![];
+        let poles = vec![
+            state.own_goal.pole_top.clone(),
+            state.own_goal.pole_bottom.clone(),
+            state.target_goal.pole_top.clone(),
+            state.target_goal.pole_bottom.clone(),
+        ];
+        // Our spells cast in earlier turns keep acting on their targets, inferred opponent ones are too uncertain
+        let effects = state.spells.active().iter().filter_map(|active| match (active.effect, active.caster) {
+            (spell_tracker::Effect::Spell(spell), Some(caster)) if active.ours => Some(Effect {
+                spell,
+                caster,
+                caster_type: EntityType::Wizard,
+                target: active.target,
+                turns_left: active.turns_left,
+            }),
+            _ => None,
+        }).collect();
+        Simulation { state, scored: [0, 0], poles, effects, orders: vec![] }
+    }
+    pub fn move_wizard(&mut self, id: i32, dest: Vector2, thrust: i32) {
+        self.orders.push(Order::Move(id, dest, thrust.max(0).min(MAX_THRUST) as f32));
+    }
+    pub fn throw(&mut self, id: i32, dest: Vector2, power: i32) {
+        self.orders.push(Order::Throw(id, dest, power.max(0).min(MAX_POWER) as f32));
+    }
+    // Paid from the magic of the caster's team, refused when the target is the caster itself or
+    // Obliviate is cast on anything but a bludger
+    pub fn cast(&mut self, caster: i32, spell: Spell, target: i32) {
+        let (caster_type, target) = match (self.index_of(caster), self.index_of(target)) {
+            (Some(c), Some(t)) if c != t => (self.state.entities[c].entity_type.clone(), &self.state.entities[t]),
+            _ => return,
+        };
+        if !spell.can_target(&target.entity_type) {
+            return;
+        }
+        let target = target.id;
+        if self.pay(&caster_type, spell.cost()) {
+            self.effects.push(Effect { spell, caster, caster_type, target, turns_left: spell.duration() });
+        }
+    }
+    // Pushes the target towards the destination right away, with a force of WINGARDIUM_FORCE per point
+    // of magic spent. A carried snaffle cannot be levitated.
+    pub fn wingardium(&mut self, caster: i32, target: i32, dest: Vector2, magic: i32) {
+        let (caster_type, target) = match (self.index_of(caster), self.index_of(target)) {
+            (Some(c), Some(t)) if c != t && self.state.entities[t].held_by().is_none() => {
+                (self.state.entities[c].entity_type.clone(), t)
+            }
+            _ => return,
+        };
+        if self.pay(&caster_type, magic) {
+            self.push(target, dest, magic as f32 * WINGARDIUM_FORCE);
+        }
+    }
+    // Advances the simulation by one turn: spells act first, then bludgers, then the moves and throws given
+    // since the last step
+    pub fn step(&mut self) {
+        self.apply_spells();
+        self.steer_bludgers();
+        self.carry_out_orders();
+        self.move_entities();
+        self.end_turn();
+    }
+    fn index_of(&self, id: i32) -> Option<usize> {
+        self.state.entities.iter().position(|e| e.id == id)
+    }
+    // Takes the magic from the caster's team when it has enough
+    fn pay(&mut self, caster_type: &EntityType, magic: i32) -> bool {
+        let left = if *caster_type == EntityType::Wizard { &mut self.state.magic } else { &mut self.state.opponent_magic };
+        if magic <= 0 || *left < magic {
+            return false;
+        }
+        *left -= magic;
+        true
+    }
+    fn carry_out_orders(&mut self) {
+        for order in std::mem::take(&mut self.orders) {
+            match order {
+                Order::Move(id, dest, thrust) => if let Some(i) = self.index_of(id) {
+                    self.push(i, dest, thrust);
+                },
+                Order::Throw(id, dest, power) => {
+                    let wizard = match self.index_of(id) {
+                        Some(i) => i,
+                        None => continue,
+                    };
+                    if let Some(snaffle) = self.state.entities[wizard].holding().and_then(|s| self.index_of(s)) {
+                        self.release(wizard, snaffle);
+                        self.push(snaffle, dest, power);
+                    }
+                }
+            }
+        }
+    }
+    fn push(&mut self, i: usize, dest: Vector2, force: f32) {
+        let collider = &mut self.state.entities[i].collider;
+        if collider.pos == dest || force == 0.0 {
+            return;
+        }
+        let direction = collider.pos.direction(dest);
+        collider.vel = collider.vel.add(direction.mul_num(force / collider.mass));
+    }
+    fn release(&mut self, wizard: usize, snaffle: usize) {
+        self.state.entities[wizard].set_holding(None);
+        self.state.entities[wizard].grab_cooldown = GRAB_COOLDOWN;
+        self.state.entities[snaffle].set_held_by(None);
+        self.state.entities[snaffle].collider.pos = self.state.entities[wizard].collider.pos;
+        self.state.entities[snaffle].collider.vel = self.state.entities[wizard].collider.vel;
+    }
+    fn apply_spells(&mut self) {
+        for effect in self.effects.clone() {
+            let (caster, target) = match (self.index_of(effect.caster), self.index_of(effect.target)) {
+                (Some(caster), Some(target)) if self.state.entities[target].held_by().is_none() => (caster, target),
+                _ => continue,
+            };
+            let caster_pos = self.state.entities[caster].collider.pos;
+            let target_pos = self.state.entities[target].collider.pos;
+            match effect.spell {
+                Spell::Petrificus => self.state.entities[target].collider.vel = Vector2::new(0.0, 0.0),
+                Spell::Accio => if let Some(power) = effect.spell.power(caster_pos.distance(target_pos)) {
+                    self.push(target, caster_pos, power);
+                },
+                Spell::Flipendo => if let Some(power) = effect.spell.power(caster_pos.distance(target_pos)) {
+                    self.push(target, target_pos.add(caster_pos.heading(target_pos)), power);
+                },
+                Spell::Obliviate => (),
+            }
+        }
+    }
+    fn is_obliviated(&self, bludger: i32, wizard: &Entity) -> bool {
+        self.effects.iter().any(|e| {
+            e.spell == Spell::Obliviate && e.target == bludger && e.caster_type == wizard.entity_type
+        })
+    }
+    fn steer_bludgers(&mut self) {
+        for i in 0..self.state.entities.len() {
+            if self.state.entities[i].entity_type != EntityType::Bludger {
+                continue;
+            }
+            let pos = self.state.entities[i].collider.pos;
+            let id = self.state.entities[i].id;
+            let last_victim = self.state.entities[i].last_victim();
+            let target = self.state.entities.iter()
+                             .filter(|e| e.is_wizard() && Some(e.id) != last_victim && !self.is_obliviated(id, e))
+                             .min_by(|a, b| {
+                                 (a.collider.pos.distance(pos) as i32)
+                                     .cmp(&(b.collider.pos.distance(pos) as i32))
+                             })
+                             .map(|e| e.collider.pos);
+            if let Some(target) = target {
+                self.push(i, target, BLUDGER_THRUST);
+            }
+        }
+    }
+    fn move_entities(&mut self) {
+        let mut time = 0.0;
+        for _ in 0..MAX_COLLISIONS {
+            match self.next_collision(1.0 - time) {
+                Some((at, collision)) => {
+                    self.advance(at);
+                    self.resolve(collision);
+                    time += at;
+                }
+                None => break,
+            }
+        }
+        self.advance(1.0 - time);
+    }
+    fn advance(&mut self, time: f32) {
+        if time <= 0.0 {
+            return;
+        }
+        for entity in self.state.entities.iter_mut() {
+            if entity.held_by().is_none() {
+                entity.collider.pos = entity.collider.pos.add(entity.collider.vel.mul_num(time));
+            }
+        }
+        self.follow_carriers();
+    }
+    fn follow_carriers(&mut self) {
+        for i in 0..self.state.entities.len() {
+            if let Some(carrier) = self.state.entities[i].held_by().and_then(|id| self.index_of(id)) {
+                self.state.entities[i].collider.pos = self.state.entities[carrier].collider.pos;
+                self.state.entities[i].collider.vel = self.state.entities[carrier].collider.vel;
+            }
+        }
+    }
+    fn next_collision(&self, remaining: f32) -> Option<(f32, Collision)> {
+        let mut first: Option<(f32, Collision)> = None;
+        let mut consider = |at: Option<f32>, collision: Collision| {
+            if let Some(at) = at {
+                if at <= remaining && first.is_none_or(|(t, _)| at < t) {
+                    first = Some((at, collision));
+                }
+            }
+        };
+        let entities = &self.state.entities;
+        for i in 0..entities.len() {
+            if entities[i].held_by().is_some() {
+                continue;
+            }
+            let a = &entities[i];
+            for (j, b) in entities.iter().enumerate().skip(i + 1) {
+                if b.held_by().is_some() {
+                    continue;
+                }
+                let reach = a.collider.radius + b.collider.radius;
+                match (&a.entity_type, &b.entity_type) {
+                    (EntityType::Snaffle, _) if b.is_wizard() => if b.can_grab() {
+                        consider(contact_time(&a.collider, &b.collider, reach), Collision::Grab(j, i))
+                    },
+                    (_, EntityType::Snaffle) if a.is_wizard() => if a.can_grab() {
+                        consider(contact_time(&a.collider, &b.collider, reach), Collision::Grab(i, j))
+                    },
+                    _ => consider(contact_time(&a.collider, &b.collider, reach), Collision::Bounce(i, j)),
+                }
+            }
+            for (p, pole) in self.poles.iter().enumerate() {
+                consider(contact_time(&a.collider, pole, a.collider.radius + pole.radius), Collision::Pole(i, p));
+            }
+            consider(self.vertical_wall_time(a), Collision::VerticalWall(i));
+            consider(horizontal_wall_time(&a.collider), Collision::HorizontalWall(i));
+        }
+        first
+    }
+    fn vertical_wall_time(&self, entity: &Entity) -> Option<f32> {
+        let collider = &entity.collider;
+        let at = wall_time(collider.pos.x, collider.vel.x, collider.radius, (WIDTH - 1) as f32)?;
+        // Snaffles fly through the goal mouth instead of bouncing
+        let y = collider.pos.y + collider.vel.y * at;
+        if entity.entity_type == EntityType::Snaffle && y > GOAL_TOP && y < GOAL_BOTTOM {
+            None
+        } else {
+            Some(at)
+        }
+    }
+    fn resolve(&mut self, collision: Collision) {
+        match collision {
+            Collision::Bounce(i, j) => {
+                let (a, b) = self.pair_mut(i, j);
+                bounce(&mut a.collider, &mut b.collider);
+                let (a, b) = self.pair_mut(i, j);
+                if a.entity_type == EntityType::Bludger && b.is_wizard() {
+                    a.set_last_victim(Some(b.id));
+                } else if b.entity_type == EntityType::Bludger && a.is_wizard() {
+                    b.set_last_victim(Some(a.id));
+                }
+            }
+            Collision::Grab(wizard, snaffle) => {
+                let (wizard_id, snaffle_id) = (self.state.entities[wizard].id, self.state.entities[snaffle].id);
+                self.state.entities[wizard].set_holding(Some(snaffle_id));
+                self.state.entities[snaffle].set_held_by(Some(wizard_id));
+                self.follow_carriers();
+            }
+            Collision::Pole(i, p) => {
+                let mut pole = self.poles[p].clone();
+                bounce(&mut self.state.entities[i].collider, &mut pole);
+            }
+            Collision::VerticalWall(i) => {
+                let vel = &mut self.state.entities[i].collider.vel;
+                vel.x = -vel.x;
+            }
+            Collision::HorizontalWall(i) => {
+                let vel = &mut self.state.entities[i].collider.vel;
+                vel.y = -vel.y;
+            }
+        }
+    }
+    fn pair_mut(&mut self, i: usize, j: usize) -> (&mut Entity, &mut Entity) {
+        let (left, right) = self.state.entities.split_at_mut(j);
+        (&mut left[i], &mut right[0])
+    }
+    fn end_turn(&mut self) {
+        for entity in self.state.entities.iter_mut() {
+            let collider = &mut entity.collider;
+            collider.pos = collider.pos.round();
+            collider.vel = collider.vel.mul_num(collider.friction).round();
+            if entity.grab_cooldown > 0 {
+                entity.grab_cooldown -= 1;
+            }
+        }
+        for effect in self.effects.iter_mut() {
+            effect.turns_left -= 1;
+        }
+        self.effects.retain(|e| e.turns_left > 0);
+        self.follow_carriers();
+        self.score_goals();
+        self.state.magic = (self.state.magic + 1).min(MAX_MAGIC);
+        self.state.opponent_magic = (self.state.opponent_magic + 1).min(MAX_MAGIC);
+    }
+    fn score_goals(&mut self) {
+        let right_line = (WIDTH - 1) as f32;
+        let mut i = 0;
+        while i < self.state.entities.len() {
+            let entity = &self.state.entities[i];
+            let scoring_team = if entity.entity_type != EntityType::Snaffle {
+                None
+            } else if entity.collider.pos.x > right_line {
+                Some(0)
+            } else if entity.collider.pos.x < 0.0 {
+                Some(1)
+            } else {
+                None
+            };
+            match scoring_team {
+                Some(team) => {
+                    self.scored[team] += 1;
+                    self.state.entities.remove(i);
+                }
+                None => i += 1,
+            }
+        }
+    }
+}
+
+fn inverse_mass(collider: &Collider) -> f32 {
+    // Goal poles have no mass and never move
+    if collider.mass > 0.0 { 1.0 / collider.mass } else { 0.0 }
+}
+
+// Earliest time within this turn at which two circles moving towards each other are `reach` apart
+fn contact_time(a: &Collider, b: &Collider, reach: f32) -> Option<f32> {
+    let offset = a.pos.sub(b.pos);
+    let closing = a.vel.sub(b.vel);
+    let approach = offset.dot(closing);
+    if approach >= 0.0 {
+        return None;
+    }
+    let gap = offset.dot(offset) - reach * reach;
+    if gap <= 0.0 {
+        return Some(0.0);
+    }
+    let speed = closing.dot(closing);
+    let discriminant = approach * approach - speed * gap;
+    if discriminant < 0.0 {
+        return None;
+    }
+    Some((-approach - discriminant.sqrt()) / speed)
+}
+
+fn wall_time(pos: f32, vel: f32, radius: f32, far_wall: f32) -> Option<f32> {
+    if vel < 0.0 {
+        Some(((radius - pos) / vel).max(0.0))
+    } else if vel > 0.0 {
+        Some(((far_wall - radius - pos) / vel).max(0.0))
+    } else {
+        None
+    }
+}
+
+fn horizontal_wall_time(collider: &Collider) -> Option<f32> {
+    wall_time(collider.pos.y, collider.vel.y, collider.radius, (HEIGHT - 1) as f32)
+}
+
+// Elastic collision where the impulse is never weaker than MIN_IMPULSE
+fn bounce(a: &mut Collider, b: &mut Collider) {
+    let inverse_a = inverse_mass(a);
+    let inverse_b = inverse_mass(b);
+    let normal = a.pos.sub(b.pos);
+    let distance_sq = normal.dot(normal);
+    if distance_sq == 0.0 {
+        return;
+    }
+    let product = normal.dot(a.vel.sub(b.vel));
+    let mut force = normal.mul_num(product / (distance_sq * (inverse_a + inverse_b)));
+    a.vel = a.vel.sub(force.mul_num(inverse_a));
+    b.vel = b.vel.add(force.mul_num(inverse_b));
+    let impulse = force.length();
+    if impulse > 0.0 && impulse < MIN_IMPULSE {
+        force = force.mul_num(MIN_IMPULSE / impulse);
+    }
+    a.vel = a.vel.sub(force.mul_num(inverse_a));
+    b.vel = b.vel.add(force.mul_num(inverse_b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS};
+    use crate::{WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+    fn body(id: i32, entity_type: EntityType, pos: (f32, f32), vel: (f32, f32)) -> Entity {
+        let (friction, mass, radius) = match entity_type {
+            EntityType::Snaffle => (SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+            EntityType::Bludger => (BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS),
+            _ => (WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+        };
+        let collider = Collider::new(Vector2::new(pos.0, pos.1), Vector2::new(vel.0, vel.1), friction, mass, radius);
+        Entity::new(id, entity_type, collider, -1)
+    }
+
+    fn simulation(entities: Vec<Entity>) -> Simulation {
+        let mut state = State::new(0);
+        state.entities = entities;
+        state.magic = 20;
+        Simulation::new(&state)
+    }
+
+    fn collider(simulation: &Simulation, id: i32) -> (Vector2, Vector2) {
+        let entity = simulation.state.entities.iter().find(|e| e.id == id).unwrap();
+        (entity.collider.pos, entity.collider.vel)
+    }
+
+    #[test]
+    fn snaffle_bounces_off_the_top_wall() {
+        let mut simulation = simulation(vec![body(5, EntityType::Snaffle, (300., 500.), (0., -400.))]);
+        simulation.step();
+        assert_eq!(collider(&simulation, 5), (Vector2::new(300., 200.), Vector2::new(0., 300.)));
+    }
+
+    #[test]
+    fn wizards_meeting_head_on_swap_velocities() {
+        let mut simulation = simulation(vec![
+            body(0, EntityType::Wizard, (5000., 3000.), (200., 0.)),
+            body(2, EntityType::Opponent, (5900., 3000.), (-200., 0.)),
+        ]);
+        simulation.step();
+        assert_eq!(collider(&simulation, 0), (Vector2::new(4900., 3000.), Vector2::new(-150., 0.)));
+        assert_eq!(collider(&simulation, 2), (Vector2::new(6000., 3000.), Vector2::new(150., 0.)));
+    }
+
+    #[test]
+    fn moves_are_carried_out_after_spells() {
+        let mut simulation = simulation(vec![
+            body(0, EntityType::Wizard, (1000., 1000.), (0., 0.)),
+            body(2, EntityType::Opponent, (8000., 3000.), (300., 0.)),
+        ]);
+        simulation.cast(0, Spell::Petrificus, 2);
+        simulation.move_wizard(2, Vector2::new(9000., 3000.), MAX_THRUST);
+        simulation.step();
+        assert_eq!(collider(&simulation, 2), (Vector2::new(8150., 3000.), Vector2::new(113., 0.)));
+        assert_eq!(simulation.state.magic, 11);
+    }
+}
@@ -0,0 +1,211 @@
+// Fallible reader for the Fantastic Bits input protocol
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::time::Instant;
+
+use crate::EntityType;
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnexpectedEof,
+    Io(io::Error),
+    WrongFieldCount { line: String, expected: usize, found: usize },
+    BadInteger { line: String, field: String },
+    UnknownEntityType(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseError::Io(error) => write!(f, "cannot read input: {}", error),
+            ParseError::WrongFieldCount { line, expected, found } =>
+                write!(f, "expected {} fields but found {} in '{}'", expected, found, line),
+            ParseError::BadInteger { line, field } => write!(f, "'{}' is not an integer in '{}'", field, line),
+            ParseError::UnknownEntityType(kind) => write!(f, "unknown entity type '{}'", kind),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> ParseError {
+        ParseError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct EntityInput {
+    pub id: i32,
+    pub entity_type: EntityType,
+    pub x: i32,
+    pub y: i32,
+    pub vx: i32,
+    pub vy: i32,
+    // 1 if the wizard is holding a Snaffle, 1 if the Snaffle is being held, id of the last victim of the bludger or -1
+    pub state: i32,
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct TurnInput {
+    pub my_score: i32,
+    pub my_magic: i32,
+    pub opponent_score: i32,
+    pub opponent_magic: i32,
+    pub entities: Vec<EntityInput>,
+    // When the first line of the turn arrived, the response time is counted from there
+    pub received: Instant,
+}
+
+// Where turns come from: stdin on CodinGame, files or recordings elsewhere
+pub trait TurnSource {
+    fn read_team_id(&mut self) -> Result<i32, ParseError>;
+    fn read_turn(&mut self) -> Result<TurnInput, ParseError>;
+}
+
+// Where the command lines of every turn go
+pub trait CommandSink {
+    fn emit(&mut self, commands: Vec<String>);
+}
+
+impl<F: FnMut(Vec<String>)> CommandSink for F {
+    fn emit(&mut self, commands: Vec<String>) {
+        self(commands)
+    }
+}
+
+pub struct StdoutSink;
+
+impl CommandSink for StdoutSink {
+    fn emit(&mut self, commands: Vec<String>) {
+        for command in commands {
+            println!("{}", command);
+        }
+    }
+}
+
+pub struct ProtocolReader<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> ProtocolReader<R> {
+    pub fn new(reader: R) -> ProtocolReader<R> {
+        ProtocolReader { reader }
+    }
+    // Next line without its line ending, a closed input is an error rather than an empty line
+    fn read_line(&mut self) -> Result<String, ParseError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(ParseError::UnexpectedEof);
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+impl<R: BufRead> TurnSource for ProtocolReader<R> {
+    // 0 if you need to score on the right of the map, 1 if you need to score on the left
+    fn read_team_id(&mut self) -> Result<i32, ParseError> {
+        let line = self.read_line()?;
+        Ok(parse_fields(&line, 1)?[0])
+    }
+    // Every line of the turn is read before any is parsed, a bad line must not leave the rest of the turn
+    // to be taken for the next one
+    fn read_turn(&mut self) -> Result<TurnInput, ParseError> {
+        let mine = self.read_line()?;
+        let received = Instant::now();
+        let opponent = self.read_line()?;
+        let line = self.read_line()?;
+        let count = parse_fields(&line, 1)?[0].max(0);
+        let lines = (0..count).map(|_| self.read_line()).collect::<Result<Vec<_>, _>>()?;
+        let mine = parse_fields(&mine, 2)?;
+        let opponent = parse_fields(&opponent, 2)?;
+        let entities = lines.iter().map(|line| parse_entity(line)).collect::<Result<Vec<_>, _>>()?;
+        Ok(TurnInput {
+            my_score: mine[0],
+            my_magic: mine[1],
+            opponent_score: opponent[0],
+            opponent_magic: opponent[1],
+            entities,
+            received,
+        })
+    }
+}
+
+fn parse_entity(line: &str) -> Result<EntityInput, ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 7 {
+        return Err(ParseError::WrongFieldCount { line: line.to_string(), expected: 7, found: fields.len() });
+    }
+    let entity_type = match fields[1] {
+        "WIZARD" => EntityType::Wizard,
+        "OPPONENT_WIZARD" => EntityType::Opponent,
+        "SNAFFLE" => EntityType::Snaffle,
+        "BLUDGER" => EntityType::Bludger,
+        other => return Err(ParseError::UnknownEntityType(other.to_string())),
+    };
+    let number = |i: usize| parse_integer(line, fields[i]);
+    Ok(EntityInput {
+        id: number(0)?,
+        entity_type,
+        x: number(2)?,
+        y: number(3)?,
+        vx: number(4)?,
+        vy: number(5)?,
+        state: number(6)?,
+    })
+}
+
+fn parse_integer(line: &str, field: &str) -> Result<i32, ParseError> {
+    field.parse::<i32>().map_err(|_| ParseError::BadInteger { line: line.to_string(), field: field.to_string() })
+}
+
+fn parse_fields(line: &str, expected: usize) -> Result<Vec<i32>, ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != expected {
+        return Err(ParseError::WrongFieldCount { line: line.to_string(), expected, found: fields.len() });
+    }
+    fields.iter().map(|field| parse_integer(line, field)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn turn(entities: &[&str]) -> String {
+        format!("0 5\n1 6\n{}\n{}\n", entities.len(), entities.join("\n"))
+    }
+
+    #[test]
+    fn bad_entity_line_only_costs_its_turn() {
+        let input = turn(&["0 WIZARD 1000 2250 0 0 0", "1 WIZARD 1000 x 0 0 0", "2 SNAFFLE 8000 3750 0 0 0"]) +
+            &turn(&["0 WIZARD 1100 2300 10 -5 1"]);
+        let mut reader = ProtocolReader::new(Cursor::new(input));
+        assert!(matches!(reader.read_turn(), Err(ParseError::BadInteger { .. })));
+        let next = reader.read_turn().unwrap();
+        assert_eq!((next.my_score, next.my_magic, next.opponent_score, next.opponent_magic), (0, 5, 1, 6));
+        assert_eq!(next.entities, vec![
+            EntityInput { id: 0, entity_type: EntityType::Wizard, x: 1100, y: 2300, vx: 10, vy: -5, state: 1 },
+        ]);
+        assert!(matches!(reader.read_turn(), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn team_id_is_a_single_integer() {
+        assert_eq!(ProtocolReader::new(Cursor::new("1\n")).read_team_id().unwrap(), 1);
+        assert!(matches!(ProtocolReader::new(Cursor::new("1 2\n")).read_team_id(),
+                         Err(ParseError::WrongFieldCount { expected: 1, found: 2, .. })));
+        assert!(matches!(ProtocolReader::new(Cursor::new("")).read_team_id(), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn entity_types_and_field_counts() {
+        assert_eq!(parse_entity("7 BLUDGER 7450 3750 -3 4 2").unwrap().entity_type, EntityType::Bludger);
+        assert_eq!(parse_entity("3 OPPONENT_WIZARD 1 2 3 4 0").unwrap().entity_type, EntityType::Opponent);
+        assert!(matches!(parse_entity("7 BROOM 1 2 3 4 0"), Err(ParseError::UnknownEntityType(_))));
+        assert!(matches!(parse_entity("7 SNAFFLE 1 2 3 4"), Err(ParseError::WrongFieldCount { expected: 7, found: 6, .. })));
+    }
+}
@@ -0,0 +1,104 @@
+// Game logs of every input line received ("< ") and command emitted ("> "), and their replay
+use std::fs;
+use std::io::{self, BufRead, Cursor, Read, Write};
+
+use crate::protocol::ProtocolReader;
+use crate::Strategy;
+
+// Passes input through while copying each line to the log
+pub struct TeeReader<R: BufRead, W: Write> {
+    inner: R,
+    log: W,
+    line_start: bool,
+}
+
+impl<R: BufRead, W: Write> TeeReader<R, W> {
+    pub fn new(inner: R, log: W) -> TeeReader<R, W> {
+        TeeReader { inner, log, line_start: true }
+    }
+}
+
+impl<R: BufRead, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = {
+            let available = self.fill_buf()?;
+            let read = available.len().min(buf.len());
+            buf[..read].copy_from_slice(&available[..read]);
+            read
+        };
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead, W: Write> BufRead for TeeReader<R, W> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        // A failing log must never cost the game, write errors are ignored
+        if let Ok(buffer) = self.inner.fill_buf() {
+            for chunk in buffer[..amt.min(buffer.len())].split_inclusive(|&b| b == b'\n') {
+                if self.line_start {
+                    let _ = self.log.write_all(b"< ");
+                }
+                let _ = self.log.write_all(chunk);
+                self.line_start = chunk.ends_with(b"\n");
+            }
+        }
+        self.inner.consume(amt)
+    }
+}
+
+pub struct Recording {
+    input: String,
+    // Commands of each turn in the order they were played
+    turns: Vec<Vec<String>>,
+}
+
+impl Recording {
+    pub fn load(path: &str) -> io::Result<Recording> {
+        let mut input = String::new();
+        let mut turns: Vec<Vec<String>> = vec![];
+        let mut in_turn = false;
+        for line in fs::read_to_string(path)?.lines() {
+            if let Some(received) = line.strip_prefix("< ") {
+                input.push_str(received);
+                input.push('\n');
+                in_turn = false;
+            } else if let Some(emitted) = line.strip_prefix("> ") {
+                if !in_turn {
+                    turns.push(vec![]);
+                    in_turn = true;
+                }
+                turns.last_mut().unwrap().push(emitted.to_string());
+            }
+        }
+        Ok(Recording { input, turns })
+    }
+}
+
+// Feeds a recorded game to the bot again and prints where its decisions differ from the recording
+pub fn replay(path: &str, strategy: &mut dyn Strategy) -> Result<(), String> {
+    let recording = Recording::load(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let mut reader = ProtocolReader::new(Cursor::new(recording.input.as_bytes()));
+    let mut turn = 0;
+    let mut differences = 0;
+    crate::play(&mut reader, strategy, &mut |commands: Vec<String>| {
+        let recorded = recording.turns.get(turn).cloned().unwrap_or_default();
+        if commands != recorded {
+            differences += 1;
+            println!("Turn {}:", turn + 1);
+            for wizard in 0..commands.len().max(recorded.len()) {
+                let before = recorded.get(wizard).map_or("", |c| c.as_str());
+                let after = commands.get(wizard).map_or("", |c| c.as_str());
+                if before != after {
+                    println!("  wizard {}: recorded '{}', replayed '{}'", wizard, before, after);
+                }
+            }
+        }
+        turn += 1;
+    }).map_err(|e| format!("cannot replay {}: {}", path, e))?;
+    println!("{} of {} turns differ", differences, turn);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+// Shots at the goal mouth, straight or banked off the top and bottom walls
+use crate::physics::WINGARDIUM_FORCE;
+use crate::{Entity, Goal, Vector2, HEIGHT, POLE_RADIUS, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS};
+
+// Points of the goal mouth aimed at
+static MOUTH_SAMPLES: i32 = 5;
+// Path spacing at which obstruction is checked
+static STEP: f32 = 200.0;
+// Free space around the path past which an obstacle no longer matters
+static SAFE_CLEARANCE: f32 = 1000.0;
+// Every bounce makes the shot a bit less predictable
+static BOUNCE_FACTOR: f32 = 0.85;
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Shot {
+    // Inside the map on the first leg of the path, where to throw or push the snaffle
+    pub aim: Vector2,
+    // Mirror image of the goal point, as far from the origin as the path is long
+    pub image: Vector2,
+    pub bounces: i32,
+    pub probability: f32,
+}
+
+pub fn throw_speed(power: i32) -> f32 {
+    power as f32 / SNAFFLE_MASS
+}
+
+pub fn wingardium_speed(magic: i32) -> f32 {
+    magic as f32 * WINGARDIUM_FORCE / SNAFFLE_MASS
+}
+
+// The likeliest shot from the origin into the goal for a snaffle leaving at the given speed, none when
+// every path is blocked or too long
+pub fn best_shot(origin: Vector2, speed: f32, goal: &Goal, obstacles: &[Entity]) -> Option<Shot> {
+    let range = speed / (1.0 - SNAFFLE_FRICTION);
+    let top = goal.pole_top.pos.y + POLE_RADIUS + SNAFFLE_RADIUS;
+    let bottom = goal.pole_bottom.pos.y - POLE_RADIUS - SNAFFLE_RADIUS;
+    (0..MOUTH_SAMPLES)
+        .map(|i| top + (bottom - top) * i as f32 / (MOUTH_SAMPLES - 1) as f32)
+        .flat_map(|y| images(y).into_iter().map(move |(image_y, bounces)| (Vector2::new(goal.center().x, image_y), bounces)))
+        .filter_map(|(image, bounces)| {
+            let length = origin.distance(image);
+            let probability = (1.0 - length / range).max(0.0) * clearance(origin, image, obstacles) *
+                BOUNCE_FACTOR.powi(bounces);
+            if probability <= 0.0 {
+                return None;
+            }
+            Some(Shot { aim: first_leg_end(origin, image), image, bounces, probability })
+        })
+        .max_by(|a, b| a.probability.total_cmp(&b.probability))
+}
+
+// Height of the wall band the snaffle centre moves in
+fn band() -> f32 {
+    (HEIGHT - 1) as f32 - 2.0 * SNAFFLE_RADIUS
+}
+
+// Mirror images of a goal point with the number of bounces of the unfolded straight path to them
+fn images(y: f32) -> Vec<(f32, i32)> {
+    let (u, band) = (y - SNAFFLE_RADIUS, band());
+    vec![(u, 0), (-u, 1), (2.0 * band - u, 1), (u - 2.0 * band, 2), (u + 2.0 * band, 2)]
+        .into_iter().map(|(u, bounces)| (u + SNAFFLE_RADIUS, bounces)).collect()
+}
+
+// Folds a point of the unfolded path back into the map
+fn fold(point: Vector2) -> Vector2 {
+    let band = band();
+    let u = (point.y - SNAFFLE_RADIUS).rem_euclid(2.0 * band);
+    let u = if u > band { 2.0 * band - u } else { u };
+    Vector2::new(point.x, u + SNAFFLE_RADIUS)
+}
+
+// Where the path first touches a wall, or the goal point itself for a straight shot
+fn first_leg_end(origin: Vector2, image: Vector2) -> Vector2 {
+    let wall = if image.y < SNAFFLE_RADIUS {
+        SNAFFLE_RADIUS
+    } else if image.y > SNAFFLE_RADIUS + band() {
+        SNAFFLE_RADIUS + band()
+    } else {
+        return image;
+    };
+    let t = (wall - origin.y) / (image.y - origin.y);
+    origin.add(image.sub(origin).mul_num(t))
+}
+
+// 1 for a path far from every obstacle down to 0 for one running into an obstacle
+fn clearance(origin: Vector2, image: Vector2, obstacles: &[Entity]) -> f32 {
+    let length = origin.distance(image);
+    let steps = (length / STEP).ceil().max(1.0) as i32;
+    let closest = (0..=steps)
+        .map(|i| fold(origin.add(image.sub(origin).mul_num(i as f32 / steps as f32))))
+        .flat_map(|point| obstacles.iter().map(move |o| point.distance(o.collider.pos) - o.collider.radius - SNAFFLE_RADIUS))
+        .fold(SAFE_CLEARANCE, f32::min);
+    if closest <= 0.0 { 0.0 } else { 0.5 + 0.5 * closest / SAFE_CLEARANCE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn every_image_folds_back_to_the_goal_point() {
+        let images = images(3000.);
+        assert_eq!(images.iter().map(|(_, bounces)| *bounces).collect::<Vec<_>>(), vec![0, 1, 1, 2, 2]);
+        for (image_y, bounces) in images {
+            let folded = fold(Vector2::new(16000., image_y));
+            assert!(close(folded.y, 3000.), "{} bounces folded to {}", bounces, folded.y);
+        }
+    }
+
+    #[test]
+    fn banked_shot_first_touches_the_wall_line() {
+        let origin = Vector2::new(8000., 2000.);
+        let images = images(3000.);
+        let top_image = Vector2::new(16000., images[1].0);
+        let aim = first_leg_end(origin, top_image);
+        assert!(close(aim.y, SNAFFLE_RADIUS));
+        assert!(aim.x > origin.x && aim.x < top_image.x);
+        // Folding the unfolded path at the aim changes nothing, it is inside the map
+        assert!(close(fold(aim).y, aim.y));
+        let bottom_image = Vector2::new(16000., images[2].0);
+        assert!(close(first_leg_end(origin, bottom_image).y, SNAFFLE_RADIUS + band()));
+        let straight = Vector2::new(16000., 3000.);
+        assert_eq!(first_leg_end(origin, straight), straight);
+    }
+}
@@ -0,0 +1,218 @@
+// Spells in effect on each entity: the ones we cast and the ones the opponent is inferred to have cast
+use crate::spells::Spell;
+use crate::{Entity, EntityType, Vector2, HEIGHT, WIDTH};
+
+// Velocity change that no friction or rounding explains
+static MIN_UNEXPLAINED: f32 = 30.0;
+// Speed an entity should at least have kept for a full stop to be a Petrificus
+static MIN_FROZEN_SPEED: f32 = 20.0;
+// Cosine between the velocity change and the caster direction needed to name the caster
+static ALIGNMENT: f32 = 0.95;
+// Slack on contact distances for the unknown thrusts applied within the turn
+static COLLISION_MARGIN: f32 = 150.0;
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum Effect {
+    Spell(Spell),
+    Wingardium,
+}
+
+impl Effect {
+    // Turns the effect is applied, starting with the turn of the cast
+    pub fn duration(&self) -> i32 {
+        match self {
+            Effect::Spell(spell) => spell.duration(),
+            Effect::Wingardium => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct ActiveSpell {
+    pub effect: Effect,
+    // Unknown for inferred spells that only some opponent could have cast
+    pub caster: Option<i32>,
+    pub target: i32,
+    // Turns the effect still applies, the current one included
+    pub turns_left: i32,
+    pub ours: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialOrd, PartialEq)]
+pub struct SpellTracker {
+    active: Vec<ActiveSpell>,
+}
+
+impl SpellTracker {
+    pub fn record(&mut self, effect: Effect, caster: i32, target: i32) {
+        self.active.push(ActiveSpell { effect, caster: Some(caster), target, turns_left: effect.duration(), ours: true });
+    }
+    // Moves to the next turn given the entities before and after it: opponent casts are inferred from
+    // velocities that changed without a collision or a known spell to explain it, then every effect
+    // loses a turn
+    pub fn update(&mut self, previous: &[Entity], current: &[Entity]) {
+        for entity in current {
+            let before = match previous.iter().find(|e| e.id == entity.id) {
+                Some(before) => before,
+                None => continue,
+            };
+            // A spell already known to act on the entity explains the change
+            let explained = self.active.iter().any(|s| s.target == entity.id);
+            if explained || may_have_collided(before, entity, previous, current) {
+                continue;
+            }
+            if let Some((effect, caster)) = infer(before, entity, previous) {
+                self.active.push(ActiveSpell { effect, caster, target: entity.id, turns_left: effect.duration(), ours: false });
+            }
+        }
+        for spell in self.active.iter_mut() {
+            spell.turns_left -= 1;
+        }
+        self.active.retain(|s| s.turns_left > 0);
+    }
+    pub fn active(&self) -> &[ActiveSpell] {
+        &self.active
+    }
+    pub fn is_active(&self, effect: Effect, target: i32) -> bool {
+        self.active.iter().any(|s| s.effect == effect && s.target == target)
+    }
+    pub fn is_levitated(&self, target: i32) -> bool {
+        self.is_active(Effect::Wingardium, target)
+    }
+    pub fn is_petrified(&self, target: i32) -> bool {
+        self.is_active(Effect::Spell(Spell::Petrificus), target)
+    }
+    // Whether one of our spells, possibly cast earlier this turn, still applies to the target
+    pub fn is_targeted(&self, target: i32) -> bool {
+        self.active.iter().any(|s| s.ours && s.target == target)
+    }
+}
+
+// Whether an entity came close enough to another one or to a wall to have bounced during the turn.
+// Paths are checked both as they would have been without a bounce, from the velocities at the start
+// of the turn, and as straight lines between the two known positions.
+fn may_have_collided(before: &Entity, after: &Entity, previous: &[Entity], current: &[Entity]) -> bool {
+    let radius = before.collider.radius + COLLISION_MARGIN;
+    let near_wall = |pos: Vector2| {
+        pos.x < radius || pos.x > (WIDTH - 1) as f32 - radius || pos.y < radius || pos.y > (HEIGHT - 1) as f32 - radius
+    };
+    if near_wall(before.collider.pos) || near_wall(after.collider.pos) {
+        return true;
+    }
+    previous.iter().filter(|other| other.id != before.id).any(|other| {
+        let other_after = current.iter().find(|e| e.id == other.id).unwrap_or(other);
+        let start = before.collider.pos.sub(other.collider.pos);
+        let unbounced = start.add(before.collider.vel.sub(other.collider.vel));
+        let observed = after.collider.pos.sub(other_after.collider.pos);
+        let reach = radius + other.collider.radius;
+        closest_approach(start, unbounced) < reach || closest_approach(start, observed) < reach
+    })
+}
+
+// Smallest distance between two bodies whose offset moves linearly from start to end
+fn closest_approach(start: Vector2, end: Vector2) -> f32 {
+    let motion = end.sub(start);
+    let length = motion.dot(motion);
+    let t = if length > 0.0 { (-start.dot(motion) / length).clamp(0.0, 1.0) } else { 0.0 };
+    start.add(motion.mul_num(t)).length()
+}
+
+// The spell and caster that explain how an entity's velocity changed, if any spell is needed at all
+fn infer(before: &Entity, after: &Entity, previous: &[Entity]) -> Option<(Effect, Option<i32>)> {
+    let collider = &after.collider;
+    let kept = before.collider.vel.mul_num(collider.friction);
+    if collider.vel == Vector2::new(0.0, 0.0) && kept.length() > MIN_FROZEN_SPEED && !after.has_snaffle() {
+        return Some((Effect::Spell(Spell::Petrificus), None));
+    }
+    // Wizards and bludgers steer themselves and carried snaffles follow their carrier
+    if after.entity_type != EntityType::Snaffle || before.has_snaffle() || after.has_snaffle() {
+        return None;
+    }
+    let change = collider.vel.sub(kept).mul_num(1.0 / collider.friction);
+    if change.length() < MIN_UNEXPLAINED {
+        return None;
+    }
+    let heading = change.mul_num(1.0 / change.length());
+    let pos = before.collider.pos;
+    // Accio pulls towards the caster and Flipendo pushes away from it
+    let caster = |spell: Spell, sign: f32| {
+        previous.iter()
+                .filter(|e| e.entity_type == EntityType::Opponent)
+                .find(|o| heading.dot(pos.direction(o.collider.pos).mul_num(sign)) > ALIGNMENT)
+                .map(|o| (Effect::Spell(spell), Some(o.id)))
+    };
+    caster(Spell::Accio, 1.0)
+        .or_else(|| caster(Spell::Flipendo, -1.0))
+        .or(Some((Effect::Wingardium, None)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collider, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS, WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+    fn body(id: i32, entity_type: EntityType, pos: (f32, f32), vel: (f32, f32)) -> Entity {
+        let (friction, mass, radius) = match entity_type {
+            EntityType::Snaffle => (SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+            _ => (WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+        };
+        let collider = Collider::new(Vector2::new(pos.0, pos.1), Vector2::new(vel.0, vel.1), friction, mass, radius);
+        Entity::new(id, entity_type, collider, 0)
+    }
+
+    #[test]
+    fn opponent_stopped_dead_was_petrified() {
+        let previous = [body(2, EntityType::Opponent, (5000., 5000.), (400., 0.))];
+        let current = [body(2, EntityType::Opponent, (5400., 5000.), (0., 0.))];
+        assert!(!may_have_collided(&previous[0], &current[0], &previous, &current));
+        assert_eq!(infer(&previous[0], &current[0], &previous), Some((Effect::Spell(Spell::Petrificus), None)));
+        // Slowing down to a stop is only friction
+        let crawling = [body(2, EntityType::Opponent, (5000., 5000.), (10., 0.))];
+        assert_eq!(infer(&crawling[0], &current[0], &crawling), None);
+    }
+
+    #[test]
+    fn snaffle_pulled_towards_an_opponent_was_accioed_by_it() {
+        let previous = vec![
+            body(2, EntityType::Opponent, (12000., 3750.), (0., 0.)),
+            body(3, EntityType::Opponent, (8000., 1000.), (0., 0.)),
+            body(5, EntityType::Snaffle, (8000., 3750.), (0., 0.)),
+        ];
+        let mut current = previous.clone();
+        current[2] = body(5, EntityType::Snaffle, (8200., 3750.), (150., 0.));
+        let mut tracker = SpellTracker::default();
+        tracker.update(&previous, &current);
+        assert_eq!(tracker.active(), &[ActiveSpell {
+            effect: Effect::Spell(Spell::Accio),
+            caster: Some(2),
+            target: 5,
+            turns_left: Spell::Accio.duration() - 1,
+            ours: false,
+        }]);
+    }
+
+    #[test]
+    fn snaffle_bouncing_off_a_wizard_is_not_a_spell() {
+        let previous = vec![
+            body(0, EntityType::Wizard, (8500., 3750.), (0., 0.)),
+            body(2, EntityType::Opponent, (12000., 3750.), (0., 0.)),
+            body(5, EntityType::Snaffle, (8000., 3750.), (400., 0.)),
+        ];
+        let mut current = previous.clone();
+        current[0] = body(0, EntityType::Wizard, (8600., 3750.), (75., 0.));
+        current[2] = body(5, EntityType::Snaffle, (7950., 3750.), (-200., 0.));
+        // The change alone points away from the opponent, like a Flipendo would
+        assert_eq!(infer(&previous[2], &current[2], &previous), Some((Effect::Spell(Spell::Flipendo), Some(2))));
+        assert!(may_have_collided(&previous[2], &current[2], &previous, &current));
+        let mut tracker = SpellTracker::default();
+        tracker.update(&previous, &current);
+        assert!(tracker.active().is_empty());
+    }
+
+    #[test]
+    fn closest_approach_within_the_turn() {
+        assert_eq!(closest_approach(Vector2::new(-300., 100.), Vector2::new(300., 100.)), 100.);
+        assert_eq!(closest_approach(Vector2::new(300., 0.), Vector2::new(600., 0.)), 300.);
+        assert_eq!(closest_approach(Vector2::new(0., 50.), Vector2::new(0., 50.)), 50.);
+    }
+}
@@ -0,0 +1,51 @@
+// Fixed cost spells of Fantastic Bits, Wingardium is handled separately since its cost is the power spent
+use crate::EntityType;
+
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum Spell {
+    Obliviate,
+    Petrificus,
+    Accio,
+    Flipendo,
+}
+
+impl Spell {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Spell::Obliviate => "OBLIVIATE",
+            Spell::Petrificus => "PETRIFICUS",
+            Spell::Accio => "ACCIO",
+            Spell::Flipendo => "FLIPENDO",
+        }
+    }
+    pub fn cost(&self) -> i32 {
+        match self {
+            Spell::Obliviate => 5,
+            Spell::Petrificus => 10,
+            Spell::Accio => 15,
+            Spell::Flipendo => 20,
+        }
+    }
+    // Obliviate only makes a bludger forget its victim, the other spells act on any entity
+    pub fn can_target(&self, entity_type: &EntityType) -> bool {
+        *self != Spell::Obliviate || *entity_type == EntityType::Bludger
+    }
+    // Number of turns the effect is applied, starting with the turn of the cast
+    pub fn duration(&self) -> i32 {
+        match self {
+            Spell::Obliviate => 4,
+            Spell::Petrificus => 1,
+            Spell::Accio => 6,
+            Spell::Flipendo => 3,
+        }
+    }
+    // Thrust applied to the target for a given caster to target distance, none for non pushing spells
+    pub fn power(&self, distance: f32) -> Option<f32> {
+        let thousands = (distance / 1000.0).powi(2);
+        match self {
+            Spell::Accio => Some((3000.0 / thousands).min(1000.0)),
+            Spell::Flipendo => Some((6000.0 / thousands).min(1000.0)),
+            _ => None,
+        }
+    }
+}
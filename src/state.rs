@@ -0,0 +1,854 @@
+// Game state as seen by our team and the decisions taken from it
+use std::time::Instant;
+
+use crate::assignment;
+use crate::intercept;
+use crate::opponent::OpponentModel;
+use crate::physics;
+use crate::shot::{self, Shot};
+use crate::protocol::TurnInput;
+use crate::command::{self, Command};
+use crate::eval::Thresholds;
+use crate::spell_tracker::SpellTracker;
+use crate::spells::Spell;
+use crate::strategy::Strategy;
+use crate::time_budget::TimeBudget;
+use crate::{Collider, Entity, EntityType, Goal, Vector2, HEIGHT, LOOKAHEAD, MAX_POWER, MAX_THRUST, MAX_TURNS, WIDTH};
+use crate::{BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS, SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS};
+use crate::{WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS};
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum ActionType {
+    Throw,
+    Move,
+    Magic,
+    Accio(i32),
+    Flipendo(i32),
+    Petrificus(i32),
+    Obliviate(i32),
+}
+
+impl ActionType {
+    pub fn spell(&self) -> Option<(Spell, i32)> {
+        match self {
+            ActionType::Accio(target) => Some((Spell::Accio, *target)),
+            ActionType::Flipendo(target) => Some((Spell::Flipendo, *target)),
+            ActionType::Petrificus(target) => Some((Spell::Petrificus, *target)),
+            ActionType::Obliviate(target) => Some((Spell::Obliviate, *target)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum TargetStrategy {
+    ClosestToWizard,
+    // Wizards split the snaffles so that the sum of their intercept times is the smallest
+    FastestIntercept,
+    // As above, guarding our goal being one more task for one of the wizards
+    FastestInterceptOrDefend,
+}
+
+// Remaining turns under which being behind calls for spending all the magic
+static LATE_TURNS: i32 = 50;
+// Remaining turns under which the endgame policy replaces the strategy
+static ENDGAME_TURNS: i32 = 20;
+// Magic kept back while our goal is under threat, enough for a Petrificus on a carrier
+static DEFENSIVE_RESERVE: i32 = 10;
+// Scoring probability a shot needs to be taken instead of the usual throw or push
+static MIN_SHOT_PROBABILITY: f32 = 0.2;
+// Headings tried in turn, in degrees off the wanted one, to keep out of the way of bludgers
+static AVOIDANCE_ANGLES: [f32; 5] = [0., 30., -30., 60., -60.];
+// Turns ahead a snaffle crossing our goal line counts as a shot to save
+static SAVE_TURNS: i32 = 8;
+// How much closer to our goal a carrier is as dangerous as a loose snaffle, about a throw
+static CARRIER_DANGER: f32 = 2000.;
+// Turns of scoreboard kept to tell how the magic of both teams is going
+static HISTORY_TURNS: usize = 5;
+
+// Which side the endgame policy plays for
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub enum Endgame {
+    // Stall and defend the lead
+    Ahead,
+    // All in magic and long shots
+    Behind,
+}
+
+// Scores and magic of both teams at the start of a turn
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct Scoreboard {
+    pub my_score: i32,
+    pub my_magic: i32,
+    pub opponent_score: i32,
+    pub opponent_magic: i32,
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct State {
+    pub entities: Vec<Entity>,
+    pub magic: i32,
+    pub my_score: i32,
+    pub opponent_score: i32,
+    pub opponent_magic: i32,
+    // Scoreboard of the last HISTORY_TURNS turns, oldest first
+    pub history: Vec<Scoreboard>,
+    // Turn being played, the first one is 1
+    pub turn: i32,
+    pub initial_snaffles: i32,
+    pub team_id: i32,
+    pub own_goal: Goal,
+    pub target_goal: Goal,
+    // Entities as the physics simulation predicts them LOOKAHEAD turns from now
+    pub(crate) predicted: Vec<Entity>,
+    pub opponent_model: OpponentModel,
+    pub spells: SpellTracker,
+    // Wizard the target strategy sent to guard our goal instead of chasing a snaffle
+    pub defender: Option<i32>,
+    pub budget: TimeBudget,
+    pub thresholds: Thresholds,
+}
+
+impl State {
+    pub fn new(team_id: i32) -> State {
+        State {
+            entities: vec![],
+            magic: 0,
+            my_score: 0,
+            opponent_score: 0,
+            opponent_magic: 0,
+            history: vec![],
+            turn: 0,
+            initial_snaffles: 0,
+            team_id,
+            own_goal: Goal::new(1 - team_id),
+            target_goal: Goal::new(team_id),
+            predicted: vec![],
+            opponent_model: OpponentModel::default(),
+            spells: SpellTracker::default(),
+            defender: None,
+            budget: TimeBudget::from_env(),
+            thresholds: Thresholds::default(),
+        }
+    }
+    pub fn update(&mut self, input: &TurnInput, init: bool) {
+        self.budget.start_at(input.received, init);
+        self.turn += 1;
+        self.magic = input.my_magic;
+        self.my_score = input.my_score;
+        self.opponent_score = input.opponent_score;
+        self.opponent_magic = input.opponent_magic;
+        self.history.push(Scoreboard {
+            my_score: input.my_score,
+            my_magic: input.my_magic,
+            opponent_score: input.opponent_score,
+            opponent_magic: input.opponent_magic,
+        });
+        if self.history.len() > HISTORY_TURNS {
+            self.history.remove(0);
+        }
+        if init {
+            self.initial_snaffles = input.entities.iter()
+                                         .filter(|e| e.entity_type == EntityType::Snaffle)
+                                         .count() as i32;
+            for entity in &input.entities {
+                let (entity_id, x, y, vx, vy) = (entity.id, entity.x, entity.y, entity.vx, entity.vy);
+                match entity.entity_type {
+                    EntityType::Wizard => self.entities.push(
+                        Entity::new(entity_id,
+                                    EntityType::Wizard,
+                                    Collider::new(
+                                        Vector2::new(x as f32, y as f32),
+                                        Vector2::new(vx as f32, vy as f32), WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+                                    entity.state)
+                    ),
+                    EntityType::Opponent => self.entities.push(
+                        Entity::new(entity_id,
+                                    EntityType::Opponent,
+                                    Collider::new(
+                                        Vector2::new(x as f32, y as f32),
+                                        Vector2::new(vx as f32, vy as f32), WIZARD_FRICTION, WIZARD_MASS, WIZARD_RADIUS),
+                                    entity.state)
+                    ),
+                    EntityType::Snaffle => self.entities.push(
+                        Entity::new(entity_id,
+                                    EntityType::Snaffle,
+                                    Collider::new(
+                                        Vector2::new(x as f32, y as f32),
+                                        Vector2::new(vx as f32, vy as f32), SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+                                    entity.state)
+                    ),
+                    EntityType::Bludger => self.entities.push(
+                        Entity::new(entity_id,
+                                    EntityType::Bludger,
+                                    Collider::new(
+                                        Vector2::new(x as f32, y as f32),
+                                        Vector2::new(vx as f32, vy as f32), BLUDGER_FRICTION, BLUDGER_MASS, BLUDGER_RADIUS),
+                                    entity.state)
+                    ),
+                }
+            }
+            self.link_carriers(input);
+        } else {
+            let previous = self.entities.clone();
+            let mut existing_snaffles = vec![];
+            for entity in &input.entities {
+                let (entity_id, x, y, vx, vy) = (entity.id, entity.x, entity.y, entity.vx, entity.vy);
+                match entity.entity_type {
+                    EntityType::Snaffle => {
+                        existing_snaffles.push(
+                            Entity::new(entity_id,
+                                        EntityType::Snaffle,
+                                        Collider::new(
+                                            Vector2::new(x as f32, y as f32),
+                                            Vector2::new(vx as f32, vy as f32), SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS),
+                                        entity.state));
+                        self.entities.iter_mut().find(|e| e.id == entity_id).unwrap().update(x, y, vx, vy, entity.state);
+                    }
+                    _ => self.entities.iter_mut().find(|e| e.id == entity_id).unwrap().update(x, y, vx, vy, entity.state)
+                }
+            }
+            let entities_to_remove = self.entities.iter()
+                                         .filter(|e| {
+                                             e.entity_type == EntityType::Snaffle &&
+                                                 existing_snaffles.iter().all(|s| s.id != e.id)
+                                         }).map(|e| e.id).collect::<Vec<i32>>();
+            let new_entities = self.entities.iter()
+                                   .filter(|e1| {
+                                       entities_to_remove.iter().all(|&id| e1.id != id)
+                                   }).cloned().collect::<Vec<Entity>>();
+            self.entities = new_entities;
+            self.link_carriers(input);
+            self.opponent_model.observe(&previous, &self.entities, &self.target_goal);
+            self.spells.update(&previous, &self.entities);
+        }
+        self.predicted = self.simulate(LOOKAHEAD).state.entities;
+        self.set_targets();
+    }
+    // A turn whose input could not be read still counts towards the end of the game, it is played on the
+    // state of the turn before
+    pub fn skip_turn(&mut self) {
+        self.budget.start_at(Instant::now(), self.turn == 0);
+        self.turn += 1;
+    }
+    // The protocol only flags carriers and carried snaffles, every flagged wizard holds the flagged
+    // snaffle closest to it
+    fn link_carriers(&mut self, input: &TurnInput) {
+        let flagged = |id: i32| input.entities.iter().any(|e| e.id == id && e.entity_type != EntityType::Bludger && e.state == 1);
+        let carriers: Vec<Entity> = self.entities.iter().filter(|e| e.is_wizard() && flagged(e.id)).cloned().collect();
+        let mut held: Vec<Entity> = self.entities.iter()
+                                         .filter(|e| e.entity_type == EntityType::Snaffle && flagged(e.id))
+                                         .cloned().collect();
+        for entity in self.entities.iter_mut() {
+            entity.set_holding(None);
+            entity.set_held_by(None);
+        }
+        for carrier in carriers {
+            let closest = held.iter().enumerate().min_by(|(_, a), (_, b)| {
+                (a.collider.pos.distance(carrier.collider.pos) as i32)
+                    .cmp(&(b.collider.pos.distance(carrier.collider.pos) as i32))
+            }).map(|(i, _)| i);
+            if let Some(i) = closest {
+                let snaffle = held.remove(i);
+                self.entities.iter_mut().find(|e| e.id == carrier.id).unwrap().set_holding(Some(snaffle.id));
+                self.entities.iter_mut().find(|e| e.id == snaffle.id).unwrap().set_held_by(Some(carrier.id));
+            }
+        }
+    }
+    // Returns the command lines of this turn, one per wizard
+    pub fn act_turn(&mut self, strategy: &mut dyn Strategy) -> Vec<String> {
+        let commands = match self.endgame() {
+            Some(mut endgame) => endgame.decide(self),
+            None => strategy.decide(self),
+        };
+        let commands = command::validate(self, commands);
+        // Our spells are tracked from the turn they are cast
+        for (wizard, command) in self.wizards().iter().zip(&commands) {
+            if let Command::Spell { kind, target, .. } = command {
+                self.spells.record(*kind, wizard.id, *target);
+            }
+        }
+        commands.iter().map(Command::line).collect()
+    }
+    // The loose snaffle crossing our goal line the soonest within SAVE_TURNS, with its path up to the line
+    pub fn incoming_shot(&self) -> Option<(Entity, Vec<Vector2>)> {
+        self.snaffles().into_iter()
+            .filter(|s| !s.has_snaffle())
+            .map(|s| {
+                let path = intercept::trajectory(&s, SAVE_TURNS);
+                (s, path)
+            })
+            .filter(|(s, path)| {
+                let last = path.last().copied().unwrap_or(s.collider.pos);
+                path.len() < SAVE_TURNS as usize && (last.x - self.own_goal.center().x).abs() < WIDTH as f32 / 2.
+            })
+            .min_by_key(|(_, path)| path.len())
+    }
+    // Where a wizard that just threw waits out its cooldown: where the loose snaffle closest to it will be
+    // once it may grab again, or in front of our goal when there is none
+    pub fn positioning_destination(&self, wizard: &Entity) -> Vector2 {
+        let later = self.simulate(wizard.grab_cooldown).state;
+        let pos = later.entities.iter().find(|e| e.id == wizard.id).map_or(wizard.collider.pos, |w| w.collider.pos);
+        later.snaffles().iter()
+             .filter(|s| !s.has_snaffle())
+             .map(|s| s.collider.pos)
+             .min_by(|a, b| (a.distance(pos) as i32).cmp(&(b.distance(pos) as i32)))
+             .unwrap_or_else(|| self.guard_position())
+    }
+    // Between our goal and the most dangerous snaffle or carrier
+    pub fn guard_position(&self) -> Vector2 {
+        let goal = self.own_goal.center();
+        match self.most_dangerous() {
+            Some(threat) => goal.add(goal.direction(threat).mul_num((goal.distance(threat) / 2.).min(2000.))),
+            None => goal,
+        }
+    }
+    // Predicted position of the loose snaffle or opponent carrier nearest to scoring on us, carriers
+    // being a throw closer than they are
+    pub fn most_dangerous(&self) -> Option<Vector2> {
+        let goal = self.own_goal.center();
+        let snaffles = self.snaffles().into_iter().filter(|s| !s.has_snaffle())
+                           .map(|s| (self.future_pos(&s), 0.));
+        let carriers = self.opponents().into_iter().filter(|o| o.has_snaffle())
+                           .map(|o| (self.future_pos(&o), CARRIER_DANGER));
+        snaffles.chain(carriers)
+                .min_by(|(a, a_bonus), (b, b_bonus)| {
+                    ((a.distance(goal) - a_bonus) as i32).cmp(&((b.distance(goal) - b_bonus) as i32))
+                })
+                .map(|(pos, _)| pos)
+    }
+    pub fn optimal_action(&self, wizard: &Entity, magic_left: &i32) -> ActionType {
+        // Defensive spells may dig into the reserve, the others may not
+        let spendable = magic_left - self.magic_reserve();
+        if wizard.has_snaffle() {
+            ActionType::Throw
+        } else if let Some(target) = self.flipendo_target(wizard, &spendable) {
+            ActionType::Flipendo(target.id)
+        } else if let Some(target) = self.petrificus_target(magic_left) {
+            ActionType::Petrificus(target.id)
+        } else if let Some(target) = self.accio_target(wizard, magic_left) {
+            ActionType::Accio(target.id)
+        } else if let Some(target) = self.obliviate_target(wizard, &spendable) {
+            ActionType::Obliviate(target.id)
+        } else if self.should_magic(&spendable) {
+            ActionType::Magic
+        } else {
+            ActionType::Move
+        }
+    }
+    pub fn should_magic(&self, magic_left: &i32) -> bool {
+        let close_to_limit = self.thresholds.near_goal;
+        // Close to target or own goal
+        let snaffle_close_to_goal = self.snaffles().iter().any(|s| {
+            let dest = self.future_pos(s);
+            self.target_goal.destination_is_close(dest, close_to_limit) ||
+                self.own_goal.destination_is_close(dest, close_to_limit)
+        });
+        // Behind late, any snaffle in the attacking half is worth pushing
+        let attacking = self.is_behind_late() && self.snaffles().iter().any(|s| {
+            self.future_pos(s).distance(self.target_goal.center()) < WIDTH as f32 / 2.
+        });
+        let urgent = snaffle_close_to_goal || attacking;
+        (*magic_left > self.thresholds.urgent_magic && urgent) || *magic_left > self.thresholds.spare_magic
+    }
+    // A team wins as soon as it has scored more than half of the snaffles the game started with
+    pub fn win_threshold(&self) -> i32 {
+        self.initial_snaffles / 2 + 1
+    }
+    pub fn goals_to_win(&self, score: i32) -> i32 {
+        self.win_threshold() - score
+    }
+    // Turns left after this one
+    pub fn remaining_turns(&self) -> i32 {
+        (MAX_TURNS - self.turn).max(0)
+    }
+    // Whether the snaffles left can still change who wins
+    pub fn is_decided(&self) -> bool {
+        let left = self.snaffles().len() as i32;
+        (self.my_score - self.opponent_score).abs() > left ||
+            self.goals_to_win(self.my_score) <= 0 || self.goals_to_win(self.opponent_score) <= 0
+    }
+    // The dedicated policy of the last turns, a tie or a decided game is played normally
+    pub fn endgame(&self) -> Option<Endgame> {
+        if self.remaining_turns() > ENDGAME_TURNS || self.is_decided() {
+            None
+        } else if self.my_score > self.opponent_score {
+            Some(Endgame::Ahead)
+        } else if self.my_score < self.opponent_score {
+            Some(Endgame::Behind)
+        } else {
+            None
+        }
+    }
+    // The next goal decides the match for one side or the other
+    pub fn is_match_point(&self) -> bool {
+        self.goals_to_win(self.my_score) <= 1 || self.goals_to_win(self.opponent_score) <= 1
+    }
+    pub fn is_behind_late(&self) -> bool {
+        self.opponent_score > self.my_score && self.remaining_turns() <= LATE_TURNS
+    }
+    // Magic kept for Petrificus and Wingardium saves while the opponent can reach a snaffle near our goal,
+    // nothing is held back when our next goal wins the match or time is running out while behind
+    pub fn magic_reserve(&self) -> i32 {
+        if self.goals_to_win(self.my_score) <= 1 || self.is_behind_late() {
+            return 0;
+        }
+        // An opponent behind a snaffle near our goal can Flipendo it in, or soon will at the rate it saves magic
+        let opponents = self.opponents();
+        let goal = self.own_goal.center();
+        let can_cast = self.opponent_magic_in(LOOKAHEAD) >= Spell::Flipendo.cost();
+        let threatened = can_cast && self.snaffles().iter().any(|s| {
+            let snaffle = self.future_pos(s);
+            snaffle.distance(goal) < self.thresholds.threat_range && opponents.iter().any(|o| {
+                let opponent = self.future_pos(o);
+                opponent.distance(snaffle) < self.thresholds.threat_range && opponent.distance(goal) > snaffle.distance(goal)
+            })
+        });
+        // Past the case above, a match point means the next goal would lose the match
+        if threatened || self.is_match_point() {
+            DEFENSIVE_RESERVE
+        } else {
+            0
+        }
+    }
+    // Opponent magic a few turns from now if it keeps saving as it did over the history, an opponent that
+    // spends is only expected to have what it has now
+    pub fn opponent_magic_in(&self, turns: i32) -> i32 {
+        let gain = match (self.history.first(), self.history.last()) {
+            (Some(first), Some(last)) if self.history.len() > 1 => {
+                (last.opponent_magic - first.opponent_magic) as f32 / (self.history.len() - 1) as f32
+            }
+            _ => 0.,
+        };
+        self.opponent_magic + (gain.max(0.) * turns as f32).round() as i32
+    }
+    pub fn flipendo_target(&self, wizard: &Entity, magic_left: &i32) -> Option<Entity> {
+        if *magic_left < Spell::Flipendo.cost() {
+            return None;
+        }
+        let turns = Spell::Flipendo.duration();
+        let scored_anyway = self.simulate(turns).scored[self.team_id as usize];
+        // Only snaffles the push actually puts into the target goal
+        self.snaffles().iter().filter(|s| {
+            !s.has_snaffle() && wizard.collider.pos.distance(s.collider.pos) < self.thresholds.flipendo_range
+        }).find(|s| {
+            let simulation = self.simulate_after(turns, |simulation| simulation.cast(wizard.id, Spell::Flipendo, s.id));
+            simulation.scored[self.team_id as usize] > scored_anyway
+        }).cloned()
+    }
+    pub fn petrificus_target(&self, magic_left: &i32) -> Option<Entity> {
+        if *magic_left < Spell::Petrificus.cost() {
+            return None;
+        }
+        // Freeze carriers about to shoot at our goal
+        self.opponents().iter().filter(|o| o.has_snaffle())
+            .find(|o| self.future_pos(o).distance(self.own_goal.center()) < self.thresholds.threat_range)
+            .cloned()
+    }
+    pub fn accio_target(&self, wizard: &Entity, magic_left: &i32) -> Option<Entity> {
+        if *magic_left < Spell::Accio.cost() {
+            return None;
+        }
+        // Pull loose snaffles heading to our goal back towards the wizard
+        let wizard_to_goal = wizard.collider.pos.distance(self.own_goal.center());
+        self.snaffles().iter().filter(|s| {
+            !s.has_snaffle() &&
+                self.own_goal.destination_is_close(self.future_pos(s), self.thresholds.near_goal) &&
+                s.collider.pos.distance(self.own_goal.center()) < wizard_to_goal &&
+                s.collider.pos.distance(wizard.collider.pos) < self.thresholds.accio_range
+        }).min_by(|a, b| {
+            (a.collider.pos.distance(wizard.collider.pos) as i32)
+                .cmp(&(b.collider.pos.distance(wizard.collider.pos) as i32))
+        }).cloned()
+    }
+    pub fn obliviate_target(&self, wizard: &Entity, magic_left: &i32) -> Option<Entity> {
+        if *magic_left < Spell::Obliviate.cost() {
+            return None;
+        }
+        // Bludgers predicted to hit the wizard
+        let wizard_future = self.future_of(wizard);
+        self.bludgers().iter()
+            .find(|b| self.future_of(b).collider.collides(&wizard_future.collider))
+            .cloned()
+    }
+    pub fn throw_destination(&self, wizard: &Entity) -> Vector2 {
+        if let Some(shot) = self.best_shot(wizard, shot::throw_speed(MAX_POWER)) {
+            return shot.aim.add(wizard.collider.vel.negate());
+        }
+        let wizard_future = self.future_of(wizard);
+        let other_wizard_dest = self.future_pos(&self.other_wizard(wizard));
+        let result = if wizard_future.collider.pos.distance(self.target_goal.center()) < self.thresholds.shooting_range {
+            self.target_goal.behind_goal()
+        } else if other_wizard_dest.distance(wizard_future.collider.pos) < self.thresholds.pass_range &&
+            other_wizard_dest.distance(self.target_goal.center()) <
+                wizard_future.collider.pos.distance(self.target_goal.center()) &&
+            !self.is_obstacles_in_between(&wizard_future.collider.pos, &other_wizard_dest) {
+            other_wizard_dest
+        } else if wizard_future.collider.pos.distance(self.target_goal.center()) > WIDTH as f32 / 2. {
+            match self.open_destination_ahead(&wizard_future, 8000) {
+                Some(dest) => dest,
+                None => self.target_goal.behind_goal()
+            }
+        } else {
+            self.target_goal.behind_goal()
+        };
+        result.add(wizard_future.collider.vel.negate())
+    }
+    // Likeliest shot at the goal, straight or banked, for a snaffle sent off now from where the entity is,
+    // worth taking only past MIN_SHOT_PROBABILITY
+    pub fn best_shot(&self, from: &Entity, speed: f32) -> Option<Shot> {
+        let obstacles: Vec<Entity> = self.opponents().iter().chain(self.bludgers().iter())
+                                         .map(|o| self.future_of(o)).collect();
+        shot::best_shot(from.collider.pos, speed, &self.target_goal, &obstacles)
+            .filter(|s| s.probability >= MIN_SHOT_PROBABILITY)
+    }
+    pub fn magic_target(&self) -> Entity {
+        // Since should magic is about "close to target or own goal", let's find closest to either
+        let mut snaffles = self.snaffles();
+        // Return random opponent if no snaffles
+        if snaffles.is_empty() {
+            return self.opponents().first().cloned().unwrap();
+        }
+        snaffles.sort_by(|a, b| {
+            (self.future_pos(a).distance(self.target_goal.center()) as i32).cmp(
+                &(self.future_pos(b).distance(self.target_goal.center()) as i32)
+            )
+        });
+        let closest_to_target = self.closest_snaffle(self.target_goal.center()).unwrap();
+        let closest_to_own_goal = self.closest_snaffle(self.own_goal.center()).unwrap();
+        if self.future_pos(&closest_to_target).distance(self.target_goal.center()) <
+            self.future_pos(&closest_to_own_goal).distance(self.own_goal.center()) {
+            closest_to_target
+        } else {
+            closest_to_own_goal
+        }
+    }
+    pub fn magic_destination(&self, target: &Entity) -> Vector2 {
+        let target_future = self.future_of(target);
+        let wizards = self.wizards();
+        //Take their future positions
+        let wiz1 = self.future_of(&wizards[0]);
+        let wiz2 = self.future_of(&wizards[1]);
+        let wiz1_is_ahead = wiz1.collider.pos.distance(self.target_goal.center()) <
+            target_future.collider.pos.distance(self.target_goal.center());
+        let wiz2_is_ahead = wiz2.collider.pos.distance(self.target_goal.center()) <
+            target_future.collider.pos.distance(self.target_goal.center());
+        let wiz1_dist = wiz1.collider.pos.distance(target_future.collider.pos);
+        let wiz2_dist = wiz2.collider.pos.distance(target_future.collider.pos);
+        //Target is close to goal, shoot at goal
+        let result =
+            if target_future.collider.pos.distance(self.target_goal.center()) < WIDTH as f32 / 2. {
+                self.target_goal.behind_goal()
+            } else {
+                match self.open_destination_ahead(&target_future, 4000) {
+                    Some(dest) => dest,
+                    None => {
+                        if wiz1_is_ahead && wiz2_is_ahead {
+                            if wiz1_dist < wiz2_dist { wiz1.collider.pos } else { wiz2.collider.pos }
+                        } else if wiz1_is_ahead
+                        { wiz1.collider.pos } else if wiz2_is_ahead
+                        { wiz2.collider.pos } else {
+                            self.target_goal.behind_goal()
+                        }
+                    }
+                }
+            };
+        result.add(target_future.collider.vel.negate())
+    }
+    pub fn open_destination_ahead(&self, target: &Entity, far: i32) -> Option<Vector2> {
+        let future_pos = self.future_pos(target);
+        // From top to bottom
+        let multiplier = if self.team_id == 0 {
+            1
+        } else { -1 } as f32;
+        let vertical_points_ahead = self.in_between_points(
+            &Vector2::new(future_pos.x + multiplier * far as f32, 0.0),
+            &Vector2::new(future_pos.x + multiplier * far as f32, 16000.0),
+            10,
+        );
+        let obstacles: Vec<Entity> = self.obstacles().iter()
+                                         .map(|o| self.future_of(o)).collect();
+        //Filter vertical points to only those that don't have obstacles between target & point
+        let possible_destinations = vertical_points_ahead.iter().filter(|p| {
+            //Filter vertical positions with direct line of sight to target
+            !self.in_between_colliders(&future_pos, p, 10).iter().any(|c| {
+                obstacles.iter().any(|o| o.collider.collides(c))
+            })
+        }).cloned().collect::<Vec<Vector2>>();
+        possible_destinations.iter().min_by(|&a, &b| {
+            (a.distance(future_pos) as i32)
+                .cmp(&(b.distance(future_pos) as i32))
+        }).cloned()
+    }
+    // Magic to push the target to the destination, capped by what can be spent. None when there is nothing
+    // to spend or the target needs no push.
+    pub fn magic_power(&self, target: &Entity, dest: &Vector2, magic_left: i32) -> Option<i32> {
+        let magic_needed = self.future_pos(target).distance(*dest) *
+            target.collider.friction / target.collider.mass;
+        let power = (magic_needed as i32).min(magic_left);
+        if power > 0 { Some(power) } else { None }
+    }
+    pub fn move_destination(&self, wizard: &Entity) -> Vector2 {
+        let destination = if let Some(target_id) = wizard.target {
+            let target = self.entities.iter().find(|e| e.id == target_id)
+                             .cloned().unwrap();
+            // Out of reach within the horizon, head for where the target is predicted
+            match intercept::solve(&wizard.collider, &target) {
+                Some(intercept) => intercept.aim,
+                None => self.future_pos(&target).add(wizard.collider.vel.negate()),
+            }
+        } else if self.defender == Some(wizard.id) {
+            self.guard_position().add(wizard.collider.vel.negate())
+        } else if !wizard.can_grab() && !wizard.has_snaffle() {
+            self.positioning_destination(wizard).add(wizard.collider.vel.negate())
+        } else {
+            Vector2::new(WIDTH as f32 / 2., HEIGHT as f32 / 2.)
+        };
+        self.avoid_bludgers(wizard, destination)
+    }
+    // Turns the move aside, by growing angles on either side, when a bludger is predicted to hit the
+    // wizard on its way
+    fn avoid_bludgers(&self, wizard: &Entity, destination: Vector2) -> Vector2 {
+        let heading = destination.sub(wizard.collider.pos);
+        AVOIDANCE_ANGLES.iter()
+            .map(|angle| wizard.collider.pos.add(heading.rotate(angle.to_radians())))
+            .find(|&dest| !self.is_hit_on_the_way(wizard, dest))
+            .unwrap_or(destination)
+    }
+    pub fn is_hit_on_the_way(&self, wizard: &Entity, dest: Vector2) -> bool {
+        let mut simulation = physics::Simulation::new(self);
+        for _ in 0..LOOKAHEAD {
+            self.opponent_model.steer(&mut simulation);
+            simulation.move_wizard(wizard.id, dest, MAX_THRUST);
+            simulation.step();
+        }
+        let bludgers = self.bludgers();
+        simulation.state.bludgers().iter().any(|b| {
+            b.last_victim() == Some(wizard.id) &&
+                bludgers.iter().any(|before| before.id == b.id && before.last_victim() != Some(wizard.id))
+        })
+    }
+    fn set_targets(&mut self) {
+        let snaffles = self.snaffles();
+        let clone_state = self.clone();
+        let target_strategy = self.target_strategy();
+        //Mutable reference to entities (Wizards)
+        let mut wizards: Vec<&mut Entity> = self.entities.iter_mut()
+                                                .filter(|e| e.entity_type == EntityType::Wizard)
+                                                .collect();
+        //Reset targets, carriers keep the snaffle they hold
+        for wizard in wizards.iter_mut() {
+            let held = wizard.holding();
+            wizard.set_target(held);
+        }
+        self.defender = None;
+        if snaffles.is_empty() { return; }
+        match target_strategy {
+            TargetStrategy::ClosestToWizard => {
+                // Wizards waiting for their grab cooldown get no snaffle, move_destination positions them instead
+                let mut taken: Option<i32> = wizards.iter().find_map(|w| w.holding());
+                for wizard in wizards.iter_mut().filter(|w| w.can_grab()) {
+                    let mut closest = clone_state.closest_snaffle(wizard.collider.pos);
+                    if let (Some(closest_id), Some(taken_id)) = (closest.as_ref().map(|s| s.id), taken) {
+                        //Same target only when there is a single snaffle
+                        if closest_id == taken_id && snaffles.len() > 1 {
+                            closest = clone_state.second_closest_snaffle(taken_id, wizard.collider.pos);
+                        }
+                    }
+                    wizard.set_target(closest.as_ref().map(|s| s.id));
+                    taken = taken.or(closest.map(|s| s.id));
+                }
+            }
+            TargetStrategy::FastestIntercept | TargetStrategy::FastestInterceptOrDefend => {
+                // Carriers keep their snaffle, the others are matched to the loose ones or to the guard post
+                let mut chasers: Vec<&mut &mut Entity> = wizards.iter_mut().filter(|w| w.can_grab()).collect();
+                let loose: Vec<&Entity> = snaffles.iter().filter(|s| !s.has_snaffle()).collect();
+                if chasers.is_empty() || loose.is_empty() { return; }
+                let mut tasks: Vec<Option<&Entity>> = loose.iter().map(|&s| Some(s)).collect();
+                if target_strategy == TargetStrategy::FastestInterceptOrDefend {
+                    tasks.push(None);
+                }
+                // Too few snaffles for everyone, they may be chased twice
+                while tasks.len() < chasers.len() {
+                    tasks.extend(loose.iter().map(|&s| Some(s)));
+                }
+                let guard = clone_state.guard_position();
+                let costs: Vec<Vec<f32>> = chasers.iter().map(|w| {
+                    tasks.iter().map(|task| {
+                        let turns = match task {
+                            Some(snaffle) => intercept::solve(&w.collider, snaffle).map(|i| i.turns),
+                            None => intercept::turns_to(&w.collider, guard),
+                        };
+                        turns.unwrap_or(intercept::HORIZON + 1) as f32
+                    }).collect()
+                }).collect();
+                for (wizard, task) in chasers.iter_mut().zip(assignment::assign(&costs)) {
+                    match tasks[task] {
+                        Some(snaffle) => wizard.set_target(Some(snaffle.id)),
+                        None => self.defender = Some(wizard.id),
+                    }
+                }
+            }
+        };
+    }
+    pub fn simulate(&self, turns: i32) -> physics::Simulation {
+        self.simulate_after(turns, |_| {})
+    }
+    // Same rollout with our orders of the first turn given by `setup`, so both can be compared
+    pub fn simulate_after(&self, turns: i32, setup: impl FnOnce(&mut physics::Simulation)) -> physics::Simulation {
+        let mut simulation = physics::Simulation::new(self);
+        setup(&mut simulation);
+        for _ in 0..turns {
+            self.opponent_model.steer(&mut simulation);
+            simulation.step();
+        }
+        simulation
+    }
+    pub fn future_of(&self, entity: &Entity) -> Entity {
+        // Scored snaffles leave the simulation, keep their last known state
+        self.predicted.iter().find(|e| e.id == entity.id)
+            .cloned().unwrap_or_else(|| entity.clone())
+    }
+    pub fn future_pos(&self, entity: &Entity) -> Vector2 {
+        self.future_of(entity).collider.pos
+    }
+    // Intercept times decide who chases what once there is a choice, and a wizard may stay back when
+    // the opponent gets to a snaffle in our half first
+    pub fn target_strategy(&self) -> TargetStrategy {
+        let chasers = self.wizards().iter().filter(|w| w.can_grab()).count();
+        let loose = self.snaffles().iter().filter(|s| !s.has_snaffle()).count();
+        if chasers == 0 || loose == 0 {
+            TargetStrategy::ClosestToWizard
+        } else if chasers > 1 && (self.is_half_contested() || self.incoming_shot().is_some()) {
+            TargetStrategy::FastestInterceptOrDefend
+        } else {
+            TargetStrategy::FastestIntercept
+        }
+    }
+    // Whether an opponent gets to a loose snaffle in our half before any of our wizards
+    pub fn is_half_contested(&self) -> bool {
+        let fastest = |wizards: Vec<Entity>, snaffle: &Entity| {
+            wizards.iter()
+                   .filter_map(|w| intercept::solve(&w.collider, snaffle).map(|i| i.turns))
+                   .min().unwrap_or(intercept::HORIZON + 1)
+        };
+        self.snaffles().iter()
+            .filter(|s| !s.has_snaffle() && s.collider.pos.distance(self.own_goal.center()) < WIDTH as f32 / 2.)
+            .any(|s| fastest(self.opponents(), s) < fastest(self.wizards(), s))
+    }
+    pub fn other_wizard(&self, wizard: &Entity) -> Entity {
+        self.wizards().iter().find(|e| e.id != wizard.id).cloned().unwrap()
+    }
+    pub fn entities_of_type(&self, entity_type: EntityType) -> Vec<Entity> {
+        self.entities.iter()
+            .filter(|e| e.entity_type == entity_type).cloned().collect()
+    }
+    pub fn wizards(&self) -> Vec<Entity> { self.entities_of_type(EntityType::Wizard) }
+    pub fn opponents(&self) -> Vec<Entity> { self.entities_of_type(EntityType::Opponent) }
+    pub fn snaffles(&self) -> Vec<Entity> { self.entities_of_type(EntityType::Snaffle) }
+    pub fn bludgers(&self) -> Vec<Entity> { self.entities_of_type(EntityType::Bludger) }
+    pub fn obstacles(&self) -> Vec<Entity> {
+        self.entities.iter()
+            .filter(|e| e.entity_type != EntityType::Wizard)
+            .cloned().collect()
+    }
+    pub fn closest_snaffle(&self, pos: Vector2) -> Option<Entity> {
+        self.snaffles().iter().min_by(|a, b| {
+            (self.future_pos(a).distance(pos) as i32).cmp(
+                &(self.future_pos(b).distance(pos) as i32)
+            )
+        }).cloned()
+    }
+    pub fn second_closest_snaffle(&self, ignore_id: i32, pos: Vector2) -> Option<Entity> {
+        self.snaffles().iter().filter(|s| s.id != ignore_id)
+            .min_by(|a, b| {
+                (self.future_pos(a).distance(pos) as i32)
+                    .cmp(&(self.future_pos(b).distance(pos) as i32))
+            }).cloned()
+    }
+    pub fn is_obstacles_in_between(&self, start: &Vector2, end: &Vector2) -> bool {
+        let obstacles: Vec<Entity> = self.obstacles().iter()
+                                         .map(|o| self.future_of(o)).collect();
+        self.in_between_colliders(start, end, 10).iter().any(|c| {
+            obstacles.iter().any(|o| o.collider.collides(c))
+        })
+    }
+    pub fn in_between_points(&self, start: &Vector2, end: &Vector2, num: i32) -> Vec<Vector2> {
+        let mut points_int_between = vec![];
+        let div = num as f32;
+        let dist = start.distance(*end);
+        let position = *start;
+        let direction = position.direction(*end);
+        for i in 1..num {
+            let new_pos = position.add(
+                direction.mul_num(i as f32 * dist / div)
+            );
+            points_int_between.push(new_pos);
+        }
+        points_int_between
+    }
+
+    pub fn in_between_colliders(&self, start: &Vector2, end: &Vector2, num: i32) -> Vec<Collider> {
+        self.in_between_points(start, end, num).iter().map(|p| {
+            Collider::new(
+                *p,
+                Vector2::new(0., 0.), SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS,
+            )
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::EntityInput;
+
+    #[test]
+    fn each_carrier_holds_the_flagged_snaffle_next_to_it() {
+        let entity = |id, entity_type, x, y, state| EntityInput { id, entity_type, x, y, vx: 0, vy: 0, state };
+        let input = TurnInput {
+            my_score: 0, my_magic: 0, opponent_score: 0, opponent_magic: 0, received: Instant::now(),
+            entities: vec![
+                entity(0, EntityType::Wizard, 3000, 3000, 1),
+                entity(1, EntityType::Wizard, 1000, 5250, 0),
+                entity(2, EntityType::Opponent, 10000, 5000, 1),
+                entity(3, EntityType::Opponent, 15000, 5250, 0),
+                entity(4, EntityType::Snaffle, 10000, 5000, 1),
+                entity(5, EntityType::Snaffle, 3100, 3000, 0),
+                entity(6, EntityType::Snaffle, 3000, 3000, 1),
+                entity(7, EntityType::Bludger, 7450, 3750, 1),
+            ],
+        };
+        let mut state = State::new(0);
+        state.update(&input, true);
+        let find = |id: i32| state.entities.iter().find(|e| e.id == id).unwrap();
+        assert_eq!((find(0).holding(), find(6).held_by()), (Some(6), Some(0)));
+        assert_eq!((find(2).holding(), find(4).held_by()), (Some(4), Some(2)));
+        assert_eq!((find(1).holding(), find(5).held_by()), (None, None));
+    }
+
+    #[test]
+    fn opponent_magic_follows_its_recent_trend() {
+        let mut state = State::new(0);
+        let scoreboard = |opponent_magic| Scoreboard { my_score: 0, my_magic: 0, opponent_score: 0, opponent_magic };
+        state.opponent_magic = 14;
+        assert_eq!(state.opponent_magic_in(3), 14);
+        state.history = vec![scoreboard(10), scoreboard(12), scoreboard(14)];
+        assert_eq!(state.opponent_magic_in(3), 20);
+        state.history = vec![scoreboard(30), scoreboard(10), scoreboard(14)];
+        assert_eq!(state.opponent_magic_in(3), 14);
+    }
+
+    #[test]
+    fn no_magic_power_without_magic_to_spend() {
+        let state = State::new(0);
+        let collider = Collider::new(Vector2::new(8000., 3750.), Vector2::new(0., 0.),
+                                     SNAFFLE_FRICTION, SNAFFLE_MASS, SNAFFLE_RADIUS);
+        let snaffle = Entity::new(4, EntityType::Snaffle, collider, 0);
+        let goal = state.target_goal.center();
+        assert_eq!(state.magic_power(&snaffle, &goal, 0), None);
+        assert_eq!(state.magic_power(&snaffle, &goal, -5), None);
+        assert_eq!(state.magic_power(&snaffle, &snaffle.collider.pos, 30), None);
+        assert_eq!(state.magic_power(&snaffle, &goal, 30), Some(30));
+    }
+}
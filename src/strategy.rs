@@ -0,0 +1,199 @@
+// Decision policies: each one looks at the state and commands every wizard of ours for the turn
+use crate::command::Command;
+use crate::genetic::GeneticSearch;
+use crate::intercept;
+use crate::mcts::MctsSearch;
+use crate::shot;
+use crate::spell_tracker::Effect;
+use crate::spells::Spell;
+use crate::{ActionType, Endgame, Entity, State, Vector2, MAX_POWER, MAX_THRUST};
+
+// Smallest Wingardium worth casting when going all in
+static ALL_IN_MIN_MAGIC: i32 = 10;
+
+pub trait Strategy {
+    // One command per wizard of ours, in the order of State::wizards
+    fn decide(&mut self, state: &State) -> Vec<Command>;
+}
+
+// Chosen at startup, the heuristic is used on CodinGame where no arguments are given. Any other name
+// is refused so that a typo cannot pass for a strategy.
+pub fn from_name(name: &str) -> Result<Box<dyn Strategy>, String> {
+    match name {
+        "" | "heuristic" => Ok(Box::new(Heuristic)),
+        "genetic" => Ok(Box::new(GeneticSearch::new(0))),
+        "mcts" => Ok(Box::new(MctsSearch::new(0))),
+        _ => Err(format!("unknown strategy '{}', expected heuristic, genetic or mcts", name)),
+    }
+}
+
+impl Strategy for GeneticSearch {
+    fn decide(&mut self, state: &State) -> Vec<Command> {
+        self.plan(state).into_iter().map(Command::from).collect()
+    }
+}
+
+impl Strategy for MctsSearch {
+    fn decide(&mut self, state: &State) -> Vec<Command> {
+        self.plan(state).into_iter().map(Command::from).collect()
+    }
+}
+
+// Commands given so far this turn, so that the second wizard knows what the first one spends and casts on
+struct Turn<'a> {
+    state: &'a State,
+    commands: Vec<Command>,
+    magic_left: i32,
+}
+
+impl<'a> Turn<'a> {
+    fn new(state: &'a State) -> Turn<'a> {
+        Turn { state, commands: vec![], magic_left: state.magic }
+    }
+    fn push(&mut self, command: Command) {
+        self.magic_left -= command.magic();
+        self.commands.push(command);
+    }
+    // Whether one of our spells, this turn's included, already acts on the target
+    fn is_targeted(&self, target: i32) -> bool {
+        self.state.spells.is_targeted(target) || self.commands.iter().any(|c| c.spell_target() == Some(target))
+    }
+    fn is_levitated(&self, target: i32) -> bool {
+        self.state.spells.is_levitated(target) || self.commands.iter().any(|c| match c {
+            Command::Spell { kind: Effect::Wingardium, target: t, .. } => *t == target,
+            _ => false,
+        })
+    }
+}
+
+// Hand written rules: throw when holding, cast when a spell target qualifies, otherwise chase
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+pub struct Heuristic;
+
+impl Strategy for Heuristic {
+    fn decide(&mut self, state: &State) -> Vec<Command> {
+        let mut turn = Turn::new(state);
+        for wizard in &state.wizards() {
+            if state.defender == Some(wizard.id) && !wizard.has_snaffle() {
+                let command = goalkeeper(&turn, wizard);
+                turn.push(command);
+                continue;
+            }
+            let mut action = state.optimal_action(wizard, &turn.magic_left);
+            // Casting on a target our spells already act on, the other wizard's included, would be wasted
+            if let Some((_, target_id)) = action.spell() {
+                if turn.is_targeted(target_id) {
+                    action = ActionType::Move;
+                }
+            }
+            // Wingardium has no hold on a carried snaffle
+            let magic_target = state.magic_target();
+            if action == ActionType::Magic && (turn.is_levitated(magic_target.id) || magic_target.held_by().is_some()) {
+                action = ActionType::Move;
+            }
+            let command = match action {
+                ActionType::Throw => Command::Throw(state.throw_destination(wizard), MAX_POWER),
+                ActionType::Magic => {
+                    let spendable = turn.magic_left - state.magic_reserve();
+                    let (dest, aim) = match state.best_shot(&magic_target, shot::wingardium_speed(spendable)) {
+                        Some(shot) => (shot.aim.add(magic_target.collider.vel.negate()), shot.image),
+                        None => {
+                            let dest = state.magic_destination(&magic_target);
+                            (dest, dest)
+                        }
+                    };
+                    match state.magic_power(&magic_target, &aim, spendable) {
+                        Some(power) => Command::wingardium(magic_target.id, dest, power),
+                        None => Command::Move(state.move_destination(wizard), MAX_THRUST),
+                    }
+                }
+                ActionType::Move => Command::Move(state.move_destination(wizard), MAX_THRUST),
+                ActionType::Accio(_) | ActionType::Flipendo(_) |
+                ActionType::Petrificus(_) | ActionType::Obliviate(_) => {
+                    let (spell, target_id) = action.spell().unwrap();
+                    Command::cast(spell, target_id)
+                }
+            };
+            turn.push(command);
+        }
+        turn.commands
+    }
+}
+
+// Saves the shot about to cross our goal line: caught when the wizard gets there in time, frozen or
+// pushed back out by magic when not. Otherwise holds the line to the most dangerous snaffle.
+fn goalkeeper(turn: &Turn, wizard: &Entity) -> Command {
+    let state = turn.state;
+    if let Some((snaffle, path)) = state.incoming_shot() {
+        if let Some(save) = intercept::solve_path(&wizard.collider, snaffle.collider.radius, &path) {
+            return Command::Move(save.aim, MAX_THRUST);
+        }
+        if !turn.is_targeted(snaffle.id) {
+            if turn.magic_left >= Spell::Petrificus.cost() {
+                return Command::cast(Spell::Petrificus, snaffle.id);
+            } else if turn.magic_left > 0 {
+                let away = snaffle.collider.pos.add(state.own_goal.center().direction(snaffle.collider.pos).mul_num(1000.));
+                return Command::wingardium(snaffle.id, away, turn.magic_left);
+            }
+        }
+    }
+    Command::Move(state.guard_position().add(wizard.collider.vel.negate()), MAX_THRUST)
+}
+
+// The last turns are played the same whatever the strategy. Carriers shoot at the goal from anywhere.
+// Ahead, the wizard closest to our goal guards it and defensive spells get all the magic; behind,
+// every scoring Flipendo and Wingardium is cast.
+impl Strategy for Endgame {
+    fn decide(&mut self, state: &State) -> Vec<Command> {
+        let endgame = *self;
+        let mut turn = Turn::new(state);
+        let wizards = state.wizards();
+        let guard = wizards.iter().min_by(|a, b| {
+            (a.collider.pos.distance(state.own_goal.center()) as i32)
+                .cmp(&(b.collider.pos.distance(state.own_goal.center()) as i32))
+        }).map(|w| w.id);
+        for wizard in &wizards {
+            if wizard.has_snaffle() {
+                let dest = state.target_goal.behind_goal().add(state.future_of(wizard).collider.vel.negate());
+                turn.push(Command::Throw(dest, MAX_POWER));
+                continue;
+            }
+            let magic_left = turn.magic_left;
+            let spell = match endgame {
+                Endgame::Ahead => state.petrificus_target(&magic_left).map(|t| (Spell::Petrificus, t.id))
+                                       .or_else(|| state.accio_target(wizard, &magic_left).map(|t| (Spell::Accio, t.id))),
+                Endgame::Behind => state.flipendo_target(wizard, &magic_left).map(|t| (Spell::Flipendo, t.id)),
+            }.filter(|(_, target_id)| !turn.is_targeted(*target_id));
+            if let Some((spell, target_id)) = spell {
+                turn.push(Command::cast(spell, target_id));
+                continue;
+            }
+            let command = match endgame {
+                Endgame::Ahead if Some(wizard.id) == guard => {
+                    Command::Move(state.guard_position().add(wizard.collider.vel.negate()), MAX_THRUST)
+                }
+                Endgame::Behind if magic_left >= ALL_IN_MIN_MAGIC => {
+                    let target = state.snaffles().into_iter()
+                                      .filter(|s| !s.has_snaffle() && !turn.is_targeted(s.id))
+                                      .min_by(|a, b| {
+                                          (state.future_pos(a).distance(state.target_goal.center()) as i32)
+                                              .cmp(&(state.future_pos(b).distance(state.target_goal.center()) as i32))
+                                      });
+                    match target {
+                        Some(target) => {
+                            let dest: Vector2 = state.target_goal.behind_goal().add(state.future_of(&target).collider.vel.negate());
+                            match state.magic_power(&target, &dest, magic_left) {
+                                Some(power) => Command::wingardium(target.id, dest, power),
+                                None => Command::Move(state.move_destination(wizard), MAX_THRUST),
+                            }
+                        }
+                        None => Command::Move(state.move_destination(wizard), MAX_THRUST),
+                    }
+                }
+                _ => Command::Move(state.move_destination(wizard), MAX_THRUST),
+            };
+            turn.push(command);
+        }
+        turn.commands
+    }
+}
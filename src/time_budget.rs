@@ -0,0 +1,49 @@
+// Per-turn response time limit, CodinGame allows a longer first turn
+use std::time::{Duration, Instant};
+
+static FIRST_TURN_LIMIT_MS: u64 = 1000;
+static TURN_LIMIT_MS: u64 = 100;
+static DEFAULT_MARGIN_MS: u64 = 15;
+
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct TimeBudget {
+    started: Instant,
+    limit: Duration,
+    margin: Duration,
+}
+
+impl TimeBudget {
+    pub fn new(margin: Duration) -> TimeBudget {
+        TimeBudget {
+            started: Instant::now(),
+            limit: Duration::from_millis(FIRST_TURN_LIMIT_MS),
+            margin,
+        }
+    }
+    // Margin can be overridden in milliseconds with QUIDDITCH_TIME_MARGIN
+    pub fn from_env() -> TimeBudget {
+        let margin = std::env::var("QUIDDITCH_TIME_MARGIN").ok()
+                                                           .and_then(|m| m.trim().parse::<u64>().ok())
+                                                           .unwrap_or(DEFAULT_MARGIN_MS);
+        TimeBudget::new(Duration::from_millis(margin))
+    }
+    pub fn start_at(&mut self, started: Instant, first_turn: bool) {
+        self.started = started;
+        self.limit = Duration::from_millis(if first_turn { FIRST_TURN_LIMIT_MS } else { TURN_LIMIT_MS });
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+    // Time left before the safety margin is reached
+    pub fn remaining(&self) -> Duration {
+        self.limit.checked_sub(self.margin)
+            .and_then(|usable| usable.checked_sub(self.elapsed()))
+            .unwrap_or_default()
+    }
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == Duration::from_millis(0)
+    }
+    pub fn report(&self) {
+        eprintln!("Turn time {:.1}ms / {}ms", self.elapsed().as_secs_f32() * 1000.0, self.limit.as_millis())
+    }
+}